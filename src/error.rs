@@ -8,4 +8,34 @@ pub enum PortAdaptorError {
     CollateralIndexOutOfBound,
     #[msg("BorrowIndexOutOfBound")]
     BorrowIndexOutOfBound,
+    #[msg("Obligation is stale and must be refreshed")]
+    StaleObligation,
+    #[msg("Reserve does not belong to the lending market")]
+    ReserveMarketMismatch,
+    #[msg("Collateral must be withdrawn from the borrowed reserve")]
+    CollateralReserveMismatch,
+    #[msg("Oracle account does not match the reserve oracle")]
+    OracleMismatch,
+    #[msg("Reserve liquidity mint is not the native mint")]
+    NotNativeMint,
+    #[msg("Reserve is stale and must be refreshed")]
+    StaleReserve,
+    #[msg("Port staking program account does not match the Port staking program id")]
+    WrongStakingProgram,
+    #[msg("Fee receiver does not match the reserve fee receiver")]
+    FeeReceiverMismatch,
+    #[msg("Authority is not allowed to act on this account")]
+    UnauthorizedAuthority,
+    #[msg("Too many reward claims for a single transaction")]
+    TooManyClaims,
+    #[msg("Reserve has no price oracle")]
+    MissingOracle,
+    #[msg("Reserves do not match the obligation's deposits and borrows")]
+    ReserveSetMismatch,
+    #[msg("Accounts chained between instructions do not match")]
+    ChainedAccountMismatch,
+    #[msg("Reserve account does not match the deserialized reserve")]
+    ReserveAccountMismatch,
+    #[msg("Loan to value ratio must be above zero")]
+    InvalidLtv,
 }
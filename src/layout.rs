@@ -0,0 +1,45 @@
+//! Byte offsets of the packed Port lending accounts read by `port_accessor`.
+//! These match the account layout of the Port program version targeted by
+//! `port-variable-rate-lending-instructions`.
+
+pub mod reserve {
+    use std::ops::Range;
+
+    pub const LAST_UPDATE_SLOT: Range<usize> = 1..9;
+    pub const LAST_UPDATE_STALE: usize = 9;
+    pub const LENDING_MARKET: Range<usize> = 10..42;
+    pub const LIQUIDITY_MINT: Range<usize> = 42..74;
+    pub const LIQUIDITY_MINT_DECIMALS: usize = 74;
+    pub const LIQUIDITY_SUPPLY: Range<usize> = 75..107;
+    pub const LIQUIDITY_FEE_RECEIVER: Range<usize> = 107..139;
+    pub const LIQUIDITY_ORACLE_TAG: Range<usize> = 139..143;
+    pub const LIQUIDITY_ORACLE: Range<usize> = 143..175;
+    pub const LIQUIDITY_AVAILABLE_AMOUNT: Range<usize> = 175..183;
+    pub const LIQUIDITY_BORROWED_AMOUNT_WADS: Range<usize> = 183..199;
+    pub const LIQUIDITY_CUMULATIVE_BORROW_RATE_WADS: Range<usize> = 199..215;
+    pub const LIQUIDITY_MARKET_PRICE: Range<usize> = 215..231;
+    pub const COLLATERAL_MINT: Range<usize> = 231..263;
+    pub const COLLATERAL_MINT_TOTAL_SUPPLY: Range<usize> = 263..271;
+    pub const COLLATERAL_SUPPLY: Range<usize> = 271..303;
+    pub const CONFIG_OPTIMAL_UTILIZATION_RATE: usize = 303;
+    pub const CONFIG_LOAN_TO_VALUE_RATIO: usize = 304;
+    pub const CONFIG_MIN_BORROW_RATE: usize = 307;
+    pub const CONFIG_OPTIMAL_BORROW_RATE: usize = 308;
+    pub const CONFIG_MAX_BORROW_RATE: usize = 309;
+    pub const CONFIG_BORROW_FEE_WAD: Range<usize> = 310..318;
+    pub const CONFIG_HOST_FEE_PERCENTAGE: usize = 326;
+    pub const CONFIG_DEPOSIT_STAKING_POOL_TAG: Range<usize> = 327..331;
+    pub const CONFIG_DEPOSIT_STAKING_POOL: Range<usize> = 331..363;
+}
+
+pub mod obligation {
+    use std::ops::Range;
+
+    pub const LAST_UPDATE_SLOT: Range<usize> = 1..9;
+    pub const LAST_UPDATE_STALE: usize = 9;
+    pub const LENDING_MARKET: Range<usize> = 10..42;
+    pub const OWNER: Range<usize> = 42..74;
+    pub const DEPOSITS_LEN: usize = 138;
+    pub const BORROWS_LEN: usize = 139;
+    pub const ENTRIES: usize = 140;
+}
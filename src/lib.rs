@@ -1,4 +1,13 @@
+//! Anchor CPI wrappers and account types for Port Finance lending and staking.
+//!
+//! The CPI wrappers take `CpiContext<'_, '_, '_, 'info, T>`, so a handler can
+//! build the context inline with `CpiContext::new` and pass it straight in.
+
 pub mod error;
+pub mod layout;
+pub mod rates;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 
 use std::io::Write;
 use std::ops::Deref;
@@ -11,41 +20,175 @@ use anchor_lang::solana_program::instruction::Instruction;
 use anchor_lang::solana_program::program::{invoke, invoke_signed};
 use anchor_lang::solana_program::program_option::COption;
 use anchor_lang::solana_program::program_pack::Pack;
+use num_traits::FromPrimitive;
 use port_staking_instructions::instruction::{
     claim_reward as port_claim_reward, create_stake_account as create_port_stake_account,
     deposit as port_staking_deposit, init_staking_pool as init_port_staking_pool,
-    withdraw as port_staking_withdraw,
+    withdraw as port_staking_withdraw, StakingInstruction,
 };
+use port_staking_instructions::math::Decimal as StakingDecimal;
 use port_staking_instructions::state::{StakeAccount, StakingPool};
+use port_variable_rate_lending_instructions::error::LendingError;
 use port_variable_rate_lending_instructions::instruction::{
     borrow_obligation_liquidity, deposit_reserve_liquidity,
     deposit_reserve_liquidity_and_obligation_collateral, redeem_reserve_collateral,
     refresh_obligation, refresh_reserve, repay_obligation_liquidity,
     withdraw_obligation_collateral, LendingInstruction,
 };
+use port_variable_rate_lending_instructions::math::{
+    Decimal, Rate, TryAdd, TryDiv, TryMul, TrySub,
+};
 use port_variable_rate_lending_instructions::state::{
-    CollateralExchangeRate, LendingMarket, Obligation, Reserve,
+    CollateralExchangeRate, FeeCalculation, LendingMarket, Obligation, Reserve,
+    INITIAL_COLLATERAL_RATE,
 };
 
 pub use port_staking_instructions::id as port_staking_id;
 pub use port_variable_rate_lending_instructions::id as port_lending_id;
 
-pub fn init_obligation<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, InitObligation<'info>>,
+pub fn token_account_balance(info: &AccountInfo) -> Result<u64> {
+    if info.owner != &spl_token::id() {
+        return Err(ProgramError::IllegalOwner.into());
+    }
+    let account = spl_token::state::Account::unpack(&info.try_borrow_data()?)?;
+    Ok(account.amount)
+}
+
+pub fn assert_port_owned(info: &AccountInfo) -> Result<()> {
+    assert_owned_by(info, &port_lending_id())
+}
+
+pub fn assert_owned_by(info: &AccountInfo, program_id: &Pubkey) -> Result<()> {
+    if info.owner != program_id {
+        return Err(ProgramError::IllegalOwner.into());
+    }
+    Ok(())
+}
+
+pub fn validate_clock(info: &AccountInfo) -> Result<()> {
+    if info.key != &anchor_lang::solana_program::sysvar::clock::id() {
+        msg!("Account {} is not the clock sysvar", info.key);
+        return Err(ProgramError::InvalidArgument.into());
+    }
+    Ok(())
+}
+
+// With the `strict` feature every wrapper that forwards a clock rejects
+// anything but the sysvar, since the clock drives Port's staleness checks.
+#[cfg(feature = "strict")]
+fn check_clock(info: &AccountInfo) -> Result<()> {
+    validate_clock(info)
+}
+
+#[cfg(not(feature = "strict"))]
+#[inline(always)]
+fn check_clock(_info: &AccountInfo) -> Result<()> {
+    Ok(())
+}
+
+fn assert_reserves_fresh(reserves: &[AccountInfo], current_slot: Slot) -> Result<()> {
+    for reserve in reserves {
+        if port_accessor::is_reserve_stale(reserve)?
+            || port_accessor::reserve_last_update_slot(reserve)? != current_slot
+        {
+            msg!("Reserve {} is stale", reserve.key());
+            return Err(error!(PortAdaptorError::StaleReserve));
+        }
+    }
+    Ok(())
+}
+
+pub fn assert_fresh(
+    obligation: &AccountInfo,
+    reserves: &[AccountInfo],
+    current_slot: Slot,
 ) -> Result<()> {
-    let ix = Instruction {
-        program_id: port_lending_id(),
-        accounts: vec![
-            AccountMeta::new(ctx.accounts.obligation.key(), false),
-            AccountMeta::new_readonly(ctx.accounts.lending_market.key(), false),
-            AccountMeta::new_readonly(ctx.accounts.obligation_owner.key(), true),
-            AccountMeta::new_readonly(ctx.accounts.clock.key(), false),
-            AccountMeta::new_readonly(ctx.accounts.rent.key(), false),
-            AccountMeta::new_readonly(ctx.accounts.spl_token_id.key(), false),
-        ],
-        data: LendingInstruction::InitObligation.pack(),
+    assert_reserves_fresh(reserves, current_slot)?;
+    if port_accessor::is_obligation_stale(obligation)?
+        || port_accessor::obligation_last_update_slot(obligation)? != current_slot
+    {
+        msg!("Obligation {} is stale", obligation.key());
+        return Err(error!(PortAdaptorError::StaleObligation));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "cpi-logging")]
+fn log_cpi(ix: &Instruction) {
+    msg!(
+        "Port CPI: program {} discriminant {:?} accounts {}",
+        ix.program_id,
+        ix.data.first(),
+        ix.accounts.len()
+    );
+}
+
+#[cfg(not(feature = "cpi-logging"))]
+#[inline(always)]
+fn log_cpi(_ix: &Instruction) {}
+
+pub fn decode_staking_instruction(data: &[u8]) -> Result<StakingInstruction> {
+    StakingInstruction::unpack(data).map_err(Into::into)
+}
+
+/// Describes a Port lending custom error code without allocating. Codes
+/// outside `LendingError` map to `None`; variants without a dedicated
+/// description get a generic one.
+pub fn map_lending_error(code: u32) -> Option<&'static str> {
+    let description = match LendingError::from_u32(code)? {
+        LendingError::InstructionUnpackError => "Failed to unpack instruction data",
+        LendingError::AlreadyInitialized => "Account is already initialized",
+        LendingError::NotRentExempt => "Lamport balance below rent-exempt threshold",
+        LendingError::InvalidMarketAuthority => "Market authority is invalid",
+        LendingError::InvalidMarketOwner => "Market owner is invalid",
+        LendingError::InvalidAccountOwner => "Input account owner is not the program address",
+        LendingError::InvalidTokenAccount => "Input token account is not valid",
+        LendingError::InvalidTokenMint => "Input token mint account is not valid",
+        LendingError::InvalidTokenProgram => "Input token program account is not valid",
+        LendingError::InvalidAmount => "Input amount is invalid",
+        LendingError::InvalidSigner => "Input account must be a signer",
+        LendingError::InvalidAccountInput => "Invalid account input",
+        LendingError::MathOverflow => "Math operation overflow",
+        LendingError::TokenTransferFailed => "Token transfer failed",
+        LendingError::InsufficientLiquidity => "Insufficient liquidity available",
+        LendingError::ReserveStale => "Reserve state needs to be refreshed",
+        LendingError::ObligationStale => "Obligation state needs to be refreshed",
+        LendingError::ObligationHealthy => "Cannot liquidate healthy obligations",
+        _ => "Port lending error",
     };
+    Some(description)
+}
 
+/// Logs the upstream `LendingError` description when a Port CPI fails with a
+/// custom error code, then passes the error through unchanged.
+pub fn with_lending_error_context<T>(result: Result<T>) -> Result<T> {
+    if let Err(Error::ProgramError(err)) = &result {
+        if let ProgramError::Custom(code) = err.program_error {
+            if let Some(description) = map_lending_error(code) {
+                msg!("Port lending error {}: {}", code, description);
+            }
+        }
+    }
+    result
+}
+
+fn assert_port_staking_program(info: &AccountInfo, staking_program_id: &Pubkey) -> Result<()> {
+    if info.key != staking_program_id {
+        msg!("Expected the Port staking program, got {}", info.key());
+        return Err(error!(PortAdaptorError::WrongStakingProgram));
+    }
+    Ok(())
+}
+
+pub fn init_obligation<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, InitObligation<'info>>,
+) -> Result<()> {
+    check_clock(&ctx.accounts.clock)?;
+    assert_owned_by(&ctx.accounts.obligation, ctx.program.key)?;
+    assert_owned_by(&ctx.accounts.lending_market, ctx.program.key)?;
+    let ix = ctx.accounts.instruction(ctx.program.key());
+
+    log_cpi(&ix);
     invoke_signed(
         &ix,
         &[
@@ -72,22 +215,61 @@ pub struct InitObligation<'info> {
     pub spl_token_id: AccountInfo<'info>,
 }
 
-pub fn deposit_reserve<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, Deposit<'info>>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InitObligationKeys {
+    pub obligation: Pubkey,
+    pub lending_market: Pubkey,
+    pub obligation_owner: Pubkey,
+}
+
+impl InitObligationKeys {
+    fn instruction(&self, program_id: Pubkey) -> Instruction {
+        Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(self.obligation, false),
+                AccountMeta::new_readonly(self.lending_market, false),
+                AccountMeta::new_readonly(self.obligation_owner, true),
+                AccountMeta::new_readonly(anchor_lang::solana_program::sysvar::clock::id(), false),
+                AccountMeta::new_readonly(anchor_lang::solana_program::sysvar::rent::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data: LendingInstruction::InitObligation.pack(),
+        }
+    }
+}
+
+pub fn init_obligation_account_metas(
+    program_id: Pubkey,
+    keys: &InitObligationKeys,
+) -> Vec<AccountMeta> {
+    keys.instruction(program_id).accounts
+}
+
+impl<'info> InitObligation<'info> {
+    pub fn keys(&self) -> InitObligationKeys {
+        InitObligationKeys {
+            obligation: self.obligation.key(),
+            lending_market: self.lending_market.key(),
+            obligation_owner: self.obligation_owner.key(),
+        }
+    }
+
+    fn instruction(&self, program_id: Pubkey) -> Instruction {
+        self.keys().instruction(program_id)
+    }
+}
+
+pub fn deposit_reserve<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Deposit<'info>>,
     amount: u64,
 ) -> Result<()> {
-    let ix = deposit_reserve_liquidity(
-        port_lending_id(),
-        amount,
-        ctx.accounts.source_liquidity.key(),
-        ctx.accounts.destination_collateral.key(),
-        ctx.accounts.reserve.key(),
-        ctx.accounts.reserve_liquidity_supply.key(),
-        ctx.accounts.reserve_collateral_mint.key(),
-        ctx.accounts.lending_market.key(),
-        ctx.accounts.transfer_authority.key(),
-    );
+    check_clock(&ctx.accounts.clock)?;
+    assert_owned_by(&ctx.accounts.reserve, ctx.program.key)?;
+    assert_owned_by(&ctx.accounts.lending_market, ctx.program.key)?;
+    let ix = ctx.accounts.instruction(ctx.program.key(), amount);
 
+    log_cpi(&ix);
     invoke_signed(
         &ix,
         &[
@@ -122,27 +304,186 @@ pub struct Deposit<'info> {
     pub token_program: AccountInfo<'info>,
 }
 
-pub fn deposit_and_collateralize<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, DepositAndCollateralize<'info>>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepositKeys {
+    pub source_liquidity: Pubkey,
+    pub destination_collateral: Pubkey,
+    pub reserve: Pubkey,
+    pub reserve_liquidity_supply: Pubkey,
+    pub reserve_collateral_mint: Pubkey,
+    pub lending_market: Pubkey,
+    pub transfer_authority: Pubkey,
+}
+
+impl DepositKeys {
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        deposit_reserve_liquidity(
+            program_id,
+            amount,
+            self.source_liquidity,
+            self.destination_collateral,
+            self.reserve,
+            self.reserve_liquidity_supply,
+            self.reserve_collateral_mint,
+            self.lending_market,
+            self.transfer_authority,
+        )
+    }
+}
+
+pub fn deposit_account_metas(program_id: Pubkey, keys: &DepositKeys) -> Vec<AccountMeta> {
+    keys.instruction(program_id, 0).accounts
+}
+
+impl<'info> Deposit<'info> {
+    pub fn keys(&self) -> DepositKeys {
+        DepositKeys {
+            source_liquidity: self.source_liquidity.key(),
+            destination_collateral: self.destination_collateral.key(),
+            reserve: self.reserve.key(),
+            reserve_liquidity_supply: self.reserve_liquidity_supply.key(),
+            reserve_collateral_mint: self.reserve_collateral_mint.key(),
+            lending_market: self.lending_market.key(),
+            transfer_authority: self.transfer_authority.key(),
+        }
+    }
+
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        self.keys().instruction(program_id, amount)
+    }
+}
+
+/// Wraps `amount` lamports from `payer` into the new `wrapped_sol` account,
+/// deposits them, then closes `wrapped_sol` back to `payer`. `wrapped_sol` must
+/// be an unallocated signer; funding it on creation sets its token balance, so
+/// no separate sync is needed. Both the reserve and `native_mint` must be for
+/// the native mint.
+pub fn deposit_native_sol<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, DepositNativeSol<'info>>,
     amount: u64,
 ) -> Result<()> {
-    let ix = deposit_reserve_liquidity_and_obligation_collateral(
-        port_lending_id(),
-        amount,
-        ctx.accounts.source_liquidity.key(),
-        ctx.accounts.user_collateral.key(),
-        ctx.accounts.reserve.key(),
-        ctx.accounts.reserve_liquidity_supply.key(),
-        ctx.accounts.reserve_collateral_mint.key(),
-        ctx.accounts.lending_market.key(),
-        ctx.accounts.destination_collateral.key(),
-        ctx.accounts.obligation.key(),
-        ctx.accounts.obligation_owner.key(),
-        ctx.accounts.transfer_authority.key(),
-        Some(ctx.accounts.stake_account.key()),
-        Some(ctx.accounts.staking_pool.key()),
+    if !port_accessor::reserve_is_native(&ctx.accounts.reserve)? {
+        msg!("Native SOL deposit requires a native mint reserve");
+        return Err(error!(PortAdaptorError::NotNativeMint));
+    }
+    if ctx.accounts.native_mint.key != &spl_token::native_mint::id() {
+        msg!(
+            "Expected the native mint, got {}",
+            ctx.accounts.native_mint.key
+        );
+        return Err(error!(PortAdaptorError::NotNativeMint));
+    }
+    let accounts = ctx.accounts;
+    let lamports = Rent::get()?
+        .minimum_balance(spl_token::state::Account::LEN)
+        .checked_add(amount)
+        .ok_or(ProgramError::from(LendingError::MathOverflow))?;
+
+    let ix = anchor_lang::solana_program::system_instruction::create_account(
+        accounts.payer.key,
+        accounts.wrapped_sol.key,
+        lamports,
+        spl_token::state::Account::LEN as u64,
+        &spl_token::id(),
     );
+    log_cpi(&ix);
+    invoke_signed(
+        &ix,
+        &[
+            accounts.payer.clone(),
+            accounts.wrapped_sol.clone(),
+            accounts.system_program.clone(),
+        ],
+        ctx.signer_seeds,
+    )?;
+
+    let ix = spl_token::instruction::initialize_account(
+        &spl_token::id(),
+        accounts.wrapped_sol.key,
+        accounts.native_mint.key,
+        accounts.payer.key,
+    )?;
+    log_cpi(&ix);
+    invoke(
+        &ix,
+        &[
+            accounts.wrapped_sol.clone(),
+            accounts.native_mint.clone(),
+            accounts.payer.clone(),
+            accounts.rent.clone(),
+            accounts.token_program.clone(),
+        ],
+    )?;
+
+    deposit_reserve(
+        CpiContext::new_with_signer(
+            ctx.program,
+            Deposit {
+                source_liquidity: accounts.wrapped_sol.clone(),
+                destination_collateral: accounts.destination_collateral,
+                reserve: accounts.reserve,
+                reserve_liquidity_supply: accounts.reserve_liquidity_supply,
+                reserve_collateral_mint: accounts.reserve_collateral_mint,
+                lending_market: accounts.lending_market,
+                lending_market_authority: accounts.lending_market_authority,
+                transfer_authority: accounts.payer.clone(),
+                clock: accounts.clock,
+                token_program: accounts.token_program.clone(),
+            },
+            ctx.signer_seeds,
+        ),
+        amount,
+    )?;
+
+    let ix = spl_token::instruction::close_account(
+        &spl_token::id(),
+        accounts.wrapped_sol.key,
+        accounts.payer.key,
+        accounts.payer.key,
+        &[],
+    )?;
+    log_cpi(&ix);
+    invoke_signed(
+        &ix,
+        &[
+            accounts.wrapped_sol,
+            accounts.payer.clone(),
+            accounts.payer,
+            accounts.token_program,
+        ],
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct DepositNativeSol<'info> {
+    pub payer: AccountInfo<'info>,
+    pub wrapped_sol: AccountInfo<'info>,
+    pub native_mint: AccountInfo<'info>,
+    pub destination_collateral: AccountInfo<'info>,
+    pub reserve: AccountInfo<'info>,
+    pub reserve_liquidity_supply: AccountInfo<'info>,
+    pub reserve_collateral_mint: AccountInfo<'info>,
+    pub lending_market: AccountInfo<'info>,
+    pub lending_market_authority: AccountInfo<'info>,
+    pub clock: AccountInfo<'info>,
+    pub rent: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+pub fn deposit_and_collateralize<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, DepositAndCollateralize<'info>>,
+    amount: u64,
+) -> Result<()> {
+    check_clock(&ctx.accounts.clock)?;
+    assert_owned_by(&ctx.accounts.reserve, ctx.program.key)?;
+    assert_owned_by(&ctx.accounts.lending_market, ctx.program.key)?;
+    assert_owned_by(&ctx.accounts.obligation, ctx.program.key)?;
+    let ix = ctx.accounts.instruction(ctx.program.key(), amount);
 
+    log_cpi(&ix);
     invoke_signed(
         &ix,
         &[
@@ -189,22 +530,134 @@ pub struct DepositAndCollateralize<'info> {
     pub port_staking_program: AccountInfo<'info>,
 }
 
-pub fn borrow<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, Borrow<'info>>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepositAndCollateralizeKeys {
+    pub source_liquidity: Pubkey,
+    pub user_collateral: Pubkey,
+    pub reserve: Pubkey,
+    pub reserve_liquidity_supply: Pubkey,
+    pub reserve_collateral_mint: Pubkey,
+    pub lending_market: Pubkey,
+    pub destination_collateral: Pubkey,
+    pub obligation: Pubkey,
+    pub obligation_owner: Pubkey,
+    pub transfer_authority: Pubkey,
+    pub stake_account: Pubkey,
+    pub staking_pool: Pubkey,
+}
+
+impl DepositAndCollateralizeKeys {
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        deposit_reserve_liquidity_and_obligation_collateral(
+            program_id,
+            amount,
+            self.source_liquidity,
+            self.user_collateral,
+            self.reserve,
+            self.reserve_liquidity_supply,
+            self.reserve_collateral_mint,
+            self.lending_market,
+            self.destination_collateral,
+            self.obligation,
+            self.obligation_owner,
+            self.transfer_authority,
+            Some(self.stake_account),
+            Some(self.staking_pool),
+        )
+    }
+}
+
+pub fn deposit_and_collateralize_account_metas(
+    program_id: Pubkey,
+    keys: &DepositAndCollateralizeKeys,
+) -> Vec<AccountMeta> {
+    keys.instruction(program_id, 0).accounts
+}
+
+impl<'info> DepositAndCollateralize<'info> {
+    pub fn keys(&self) -> DepositAndCollateralizeKeys {
+        DepositAndCollateralizeKeys {
+            source_liquidity: self.source_liquidity.key(),
+            user_collateral: self.user_collateral.key(),
+            reserve: self.reserve.key(),
+            reserve_liquidity_supply: self.reserve_liquidity_supply.key(),
+            reserve_collateral_mint: self.reserve_collateral_mint.key(),
+            lending_market: self.lending_market.key(),
+            destination_collateral: self.destination_collateral.key(),
+            obligation: self.obligation.key(),
+            obligation_owner: self.obligation_owner.key(),
+            transfer_authority: self.transfer_authority.key(),
+            stake_account: self.stake_account.key(),
+            staking_pool: self.staking_pool.key(),
+        }
+    }
+
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        self.keys().instruction(program_id, amount)
+    }
+
+    pub fn validated(self, staking_program_id: &Pubkey) -> Result<Self> {
+        assert_port_staking_program(&self.port_staking_program, staking_program_id)?;
+        Ok(self)
+    }
+}
+
+pub fn deposit_and_collateralize_up_to<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, DepositAndCollateralize<'info>>,
+    target_collateral: u64,
+) -> Result<u64> {
+    let deposited = {
+        let data = ctx.accounts.obligation.try_borrow_data()?;
+        let obligation = PortObligation::try_deserialize(&mut &data[..])?;
+        obligation
+            .deposits
+            .iter()
+            .find(|d| d.deposit_reserve == ctx.accounts.reserve.key())
+            .map_or(0, |d| d.deposited_amount)
+    };
+    if deposited >= target_collateral {
+        return Ok(0);
+    }
+    let amount = {
+        let data = ctx.accounts.reserve.try_borrow_data()?;
+        let reserve = PortReserve::try_deserialize(&mut &data[..])?;
+        reserve
+            .collateral_exchange_rate()?
+            .decimal_collateral_to_liquidity(Decimal::from(target_collateral - deposited))?
+            .try_ceil_u64()?
+    };
+
+    deposit_and_collateralize(ctx, amount)?;
+    Ok(amount)
+}
+
+/// Port stakes the minted collateral inside the deposit instruction when a
+/// stake account and staking pool are passed, so no separate `port_stake` is
+/// issued; that instruction can only be signed by the lending program. Returns
+/// the collateral amount added to the stake account.
+pub fn deposit_collateralize_and_stake<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, DepositAndCollateralize<'info>>,
     amount: u64,
-) -> Result<()> {
-    let ix = borrow_obligation_liquidity(
-        port_lending_id(),
-        amount,
-        ctx.accounts.source_liquidity.key(),
-        ctx.accounts.destination_liquidity.key(),
-        ctx.accounts.reserve.key(),
-        ctx.accounts.reserve_fee_receiver.key(),
-        ctx.accounts.obligation.key(),
-        ctx.accounts.lending_market.key(),
-        ctx.accounts.obligation_owner.key(),
-    );
+) -> Result<u64> {
+    let stake_account = ctx.accounts.stake_account.clone();
+    let staked_before = {
+        let data = stake_account.try_borrow_data()?;
+        PortStakeAccount::try_deserialize(&mut &data[..])?.deposited_amount
+    };
+    deposit_and_collateralize(ctx, amount)?;
+    let data = stake_account.try_borrow_data()?;
+    let staked_after = PortStakeAccount::try_deserialize(&mut &data[..])?.deposited_amount;
+    Ok(staked_after.saturating_sub(staked_before))
+}
 
+pub fn borrow<'info>(ctx: CpiContext<'_, '_, '_, 'info, Borrow<'info>>, amount: u64) -> Result<()> {
+    check_clock(&ctx.accounts.clock)?;
+    assert_owned_by(&ctx.accounts.reserve, ctx.program.key)?;
+    assert_owned_by(&ctx.accounts.lending_market, ctx.program.key)?;
+    assert_owned_by(&ctx.accounts.obligation, ctx.program.key)?;
+    let ix = ctx.accounts.instruction(ctx.program.key(), amount);
+
+    log_cpi(&ix);
     invoke_signed(
         &ix,
         &[
@@ -239,21 +692,87 @@ pub struct Borrow<'info> {
     pub token_program: AccountInfo<'info>,
 }
 
-pub fn repay<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, Repay<'info>>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BorrowKeys {
+    pub source_liquidity: Pubkey,
+    pub destination_liquidity: Pubkey,
+    pub reserve: Pubkey,
+    pub reserve_fee_receiver: Pubkey,
+    pub obligation: Pubkey,
+    pub lending_market: Pubkey,
+    pub obligation_owner: Pubkey,
+}
+
+impl BorrowKeys {
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        borrow_obligation_liquidity(
+            program_id,
+            amount,
+            self.source_liquidity,
+            self.destination_liquidity,
+            self.reserve,
+            self.reserve_fee_receiver,
+            self.obligation,
+            self.lending_market,
+            self.obligation_owner,
+        )
+    }
+}
+
+pub fn borrow_account_metas(program_id: Pubkey, keys: &BorrowKeys) -> Vec<AccountMeta> {
+    keys.instruction(program_id, 0).accounts
+}
+
+impl<'info> Borrow<'info> {
+    pub fn keys(&self) -> BorrowKeys {
+        BorrowKeys {
+            source_liquidity: self.source_liquidity.key(),
+            destination_liquidity: self.destination_liquidity.key(),
+            reserve: self.reserve.key(),
+            reserve_fee_receiver: self.reserve_fee_receiver.key(),
+            obligation: self.obligation.key(),
+            lending_market: self.lending_market.key(),
+            obligation_owner: self.obligation_owner.key(),
+        }
+    }
+
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        self.keys().instruction(program_id, amount)
+    }
+
+    pub fn assert_fee_receiver(&self) -> Result<()> {
+        let expected = port_accessor::reserve_borrow_fee_receiver(&self.reserve)?;
+        if self.reserve_fee_receiver.key() != expected {
+            msg!(
+                "Expected fee receiver {}, got {}",
+                expected,
+                self.reserve_fee_receiver.key()
+            );
+            return Err(error!(PortAdaptorError::FeeReceiverMismatch));
+        }
+        Ok(())
+    }
+}
+
+pub fn borrow_checked<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Borrow<'info>>,
     amount: u64,
 ) -> Result<()> {
-    let ix = repay_obligation_liquidity(
-        port_lending_id(),
-        amount,
-        ctx.accounts.source_liquidity.key(),
-        ctx.accounts.destination_liquidity.key(),
-        ctx.accounts.reserve.key(),
-        ctx.accounts.obligation.key(),
-        ctx.accounts.lending_market.key(),
-        ctx.accounts.transfer_authority.key(),
-    );
+    if port_accessor::is_obligation_stale(&ctx.accounts.obligation)? {
+        msg!("Obligation must be refreshed before borrowing");
+        return Err(error!(PortAdaptorError::StaleObligation));
+    }
+    borrow(ctx, amount)
+}
 
+pub fn repay<'info>(ctx: CpiContext<'_, '_, '_, 'info, Repay<'info>>, amount: u64) -> Result<()> {
+    check_clock(&ctx.accounts.clock)?;
+    assert_owned_by(&ctx.accounts.reserve, ctx.program.key)?;
+    assert_owned_by(&ctx.accounts.lending_market, ctx.program.key)?;
+    assert_owned_by(&ctx.accounts.obligation, ctx.program.key)?;
+    let ix = ctx.accounts.instruction(ctx.program.key(), amount);
+
+    log_cpi(&ix);
     invoke_signed(
         &ix,
         &[
@@ -284,23 +803,98 @@ pub struct Repay<'info> {
     pub token_program: AccountInfo<'info>,
 }
 
-pub fn withdraw<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, Withdraw<'info>>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RepayKeys {
+    pub source_liquidity: Pubkey,
+    pub destination_liquidity: Pubkey,
+    pub reserve: Pubkey,
+    pub obligation: Pubkey,
+    pub lending_market: Pubkey,
+    pub transfer_authority: Pubkey,
+}
+
+impl RepayKeys {
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        repay_obligation_liquidity(
+            program_id,
+            amount,
+            self.source_liquidity,
+            self.destination_liquidity,
+            self.reserve,
+            self.obligation,
+            self.lending_market,
+            self.transfer_authority,
+        )
+    }
+}
+
+pub fn repay_account_metas(program_id: Pubkey, keys: &RepayKeys) -> Vec<AccountMeta> {
+    keys.instruction(program_id, 0).accounts
+}
+
+impl<'info> Repay<'info> {
+    pub fn keys(&self) -> RepayKeys {
+        RepayKeys {
+            source_liquidity: self.source_liquidity.key(),
+            destination_liquidity: self.destination_liquidity.key(),
+            reserve: self.reserve.key(),
+            obligation: self.obligation.key(),
+            lending_market: self.lending_market.key(),
+            transfer_authority: self.transfer_authority.key(),
+        }
+    }
+
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        self.keys().instruction(program_id, amount)
+    }
+
+    /// Anyone may repay an obligation; the transfer authority only has to own
+    /// the source liquidity account.
+    pub fn assert_transfer_authority(&self) -> Result<()> {
+        let source = spl_token::state::Account::unpack(&self.source_liquidity.try_borrow_data()?)?;
+        if source.owner != self.transfer_authority.key() {
+            msg!(
+                "Transfer authority {} does not own {}",
+                self.transfer_authority.key(),
+                self.source_liquidity.key()
+            );
+            return Err(error!(PortAdaptorError::UnauthorizedAuthority));
+        }
+        Ok(())
+    }
+}
+
+pub fn repay_max<'info>(ctx: CpiContext<'_, '_, '_, 'info, Repay<'info>>) -> Result<u64> {
+    let debt = {
+        let data = ctx.accounts.obligation.try_borrow_data()?;
+        let obligation = PortObligation::try_deserialize(&mut &data[..])?;
+        obligation
+            .borrows
+            .iter()
+            .find(|b| b.borrow_reserve == ctx.accounts.reserve.key())
+            .map_or(Ok(0), |b| b.borrowed_amount_wads.try_ceil_u64())?
+    };
+    let balance = token_account_balance(&ctx.accounts.source_liquidity)?;
+
+    let amount = debt.min(balance);
+    if amount == 0 {
+        return Ok(0);
+    }
+    repay(ctx, amount)?;
+    Ok(amount)
+}
+
+pub fn withdraw<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Withdraw<'info>>,
     amount: u64,
 ) -> Result<()> {
-    let ix = withdraw_obligation_collateral(
-        port_lending_id(),
-        amount,
-        ctx.accounts.source_collateral.key(),
-        ctx.accounts.destination_collateral.key(),
-        ctx.accounts.reserve.key(),
-        ctx.accounts.obligation.key(),
-        ctx.accounts.lending_market.key(),
-        ctx.accounts.obligation_owner.key(),
-        Some(ctx.accounts.stake_account.key()),
-        Some(ctx.accounts.staking_pool.key()),
-    );
+    check_clock(&ctx.accounts.clock)?;
+    assert_owned_by(&ctx.accounts.reserve, ctx.program.key)?;
+    assert_owned_by(&ctx.accounts.lending_market, ctx.program.key)?;
+    assert_owned_by(&ctx.accounts.obligation, ctx.program.key)?;
+    let ix = ctx.accounts.instruction(ctx.program.key(), amount);
 
+    log_cpi(&ix);
     invoke_signed(
         &ix,
         &[
@@ -323,6 +917,24 @@ pub fn withdraw<'a, 'b, 'c, 'info>(
     .map_err(Into::into)
 }
 
+/// Port unstakes the withdrawn collateral from `stake_account`, so the withdraw
+/// is capped at the amount staked there. Returns the amount withdrawn.
+pub fn withdraw_respecting_stake<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Withdraw<'info>>,
+    amount: u64,
+) -> Result<u64> {
+    let staked = {
+        let data = ctx.accounts.stake_account.try_borrow_data()?;
+        PortStakeAccount::try_deserialize(&mut &data[..])?.deposited_amount
+    };
+    let amount = amount.min(staked);
+    if amount == 0 {
+        return Ok(0);
+    }
+    withdraw(ctx, amount)?;
+    Ok(amount)
+}
+
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
     pub source_collateral: AccountInfo<'info>,
@@ -339,22 +951,84 @@ pub struct Withdraw<'info> {
     pub port_staking_program: AccountInfo<'info>,
 }
 
-pub fn redeem<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, Redeem<'info>>,
-    amount: u64,
-) -> Result<()> {
-    let ix = redeem_reserve_collateral(
-        port_lending_id(),
-        amount,
-        ctx.accounts.source_collateral.key(),
-        ctx.accounts.destination_liquidity.key(),
-        ctx.accounts.reserve.key(),
-        ctx.accounts.reserve_collateral_mint.key(),
-        ctx.accounts.reserve_liquidity_supply.key(),
-        ctx.accounts.lending_market.key(),
-        ctx.accounts.transfer_authority.key(),
-    );
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WithdrawKeys {
+    pub source_collateral: Pubkey,
+    pub destination_collateral: Pubkey,
+    pub reserve: Pubkey,
+    pub obligation: Pubkey,
+    pub lending_market: Pubkey,
+    pub obligation_owner: Pubkey,
+    pub stake_account: Pubkey,
+    pub staking_pool: Pubkey,
+}
+
+impl WithdrawKeys {
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        withdraw_obligation_collateral(
+            program_id,
+            amount,
+            self.source_collateral,
+            self.destination_collateral,
+            self.reserve,
+            self.obligation,
+            self.lending_market,
+            self.obligation_owner,
+            Some(self.stake_account),
+            Some(self.staking_pool),
+        )
+    }
+}
+
+pub fn withdraw_account_metas(program_id: Pubkey, keys: &WithdrawKeys) -> Vec<AccountMeta> {
+    keys.instruction(program_id, 0).accounts
+}
+
+impl<'info> Withdraw<'info> {
+    pub fn keys(&self) -> WithdrawKeys {
+        WithdrawKeys {
+            source_collateral: self.source_collateral.key(),
+            destination_collateral: self.destination_collateral.key(),
+            reserve: self.reserve.key(),
+            obligation: self.obligation.key(),
+            lending_market: self.lending_market.key(),
+            obligation_owner: self.obligation_owner.key(),
+            stake_account: self.stake_account.key(),
+            staking_pool: self.staking_pool.key(),
+        }
+    }
+
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        self.keys().instruction(program_id, amount)
+    }
+
+    /// Withdrawing, like borrowing, must be signed by the obligation owner.
+    pub fn assert_obligation_owner(&self) -> Result<()> {
+        let owner = port_accessor::obligation_owner(&self.obligation)?;
+        if owner != self.obligation_owner.key() {
+            msg!(
+                "Expected obligation owner {}, got {}",
+                owner,
+                self.obligation_owner.key()
+            );
+            return Err(error!(PortAdaptorError::UnauthorizedAuthority));
+        }
+        Ok(())
+    }
+
+    pub fn validated(self, staking_program_id: &Pubkey) -> Result<Self> {
+        assert_port_staking_program(&self.port_staking_program, staking_program_id)?;
+        Ok(self)
+    }
+}
+
+pub fn redeem<'info>(ctx: CpiContext<'_, '_, '_, 'info, Redeem<'info>>, amount: u64) -> Result<()> {
+    check_clock(&ctx.accounts.clock)?;
+    assert_owned_by(&ctx.accounts.reserve, ctx.program.key)?;
+    assert_owned_by(&ctx.accounts.lending_market, ctx.program.key)?;
+    let ix = ctx.accounts.instruction(ctx.program.key(), amount);
 
+    log_cpi(&ix);
     invoke_signed(
         &ix,
         &[
@@ -389,79 +1063,496 @@ pub struct Redeem<'info> {
     pub clock: AccountInfo<'info>,
 }
 
-pub fn refresh_port_reserve<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, RefreshReserve<'info>>,
-) -> Result<()> {
-    let oracle = ctx.remaining_accounts;
-    let ix = refresh_reserve(
-        port_lending_id(),
-        ctx.accounts.reserve.key(),
-        oracle
-            .first()
-            .map_or(COption::None, |k| COption::Some(k.key())),
-    );
-    let mut accounts = vec![ctx.accounts.reserve, ctx.accounts.clock, ctx.program];
-    accounts.extend(oracle.into_iter().next());
-    invoke(&ix, &accounts).map_err(Into::into)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RedeemKeys {
+    pub source_collateral: Pubkey,
+    pub destination_liquidity: Pubkey,
+    pub reserve: Pubkey,
+    pub reserve_collateral_mint: Pubkey,
+    pub reserve_liquidity_supply: Pubkey,
+    pub lending_market: Pubkey,
+    pub transfer_authority: Pubkey,
 }
 
-#[derive(Accounts)]
-pub struct RefreshReserve<'info> {
-    pub reserve: AccountInfo<'info>,
-    pub clock: AccountInfo<'info>,
+impl RedeemKeys {
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        redeem_reserve_collateral(
+            program_id,
+            amount,
+            self.source_collateral,
+            self.destination_liquidity,
+            self.reserve,
+            self.reserve_collateral_mint,
+            self.reserve_liquidity_supply,
+            self.lending_market,
+            self.transfer_authority,
+        )
+    }
 }
 
-pub fn refresh_port_obligation<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, RefreshObligation<'info>>,
-) -> Result<()> {
-    let reserves = ctx.remaining_accounts;
-    let ix = refresh_obligation(
-        port_lending_id(),
-        ctx.accounts.obligation.key(),
-        reserves.iter().map(|info| info.key()).collect(),
-    );
-    let mut account_infos = vec![ctx.accounts.obligation, ctx.accounts.clock];
-    account_infos.extend(reserves);
-    account_infos.push(ctx.program);
-    invoke(&ix, &account_infos).map_err(Into::into)
+pub fn redeem_account_metas(program_id: Pubkey, keys: &RedeemKeys) -> Vec<AccountMeta> {
+    keys.instruction(program_id, 0).accounts
 }
 
-#[derive(Accounts)]
-pub struct RefreshObligation<'info> {
-    pub obligation: AccountInfo<'info>,
-    pub clock: AccountInfo<'info>,
+impl<'info> Redeem<'info> {
+    pub fn keys(&self) -> RedeemKeys {
+        RedeemKeys {
+            source_collateral: self.source_collateral.key(),
+            destination_liquidity: self.destination_liquidity.key(),
+            reserve: self.reserve.key(),
+            reserve_collateral_mint: self.reserve_collateral_mint.key(),
+            reserve_liquidity_supply: self.reserve_liquidity_supply.key(),
+            lending_market: self.lending_market.key(),
+            transfer_authority: self.transfer_authority.key(),
+        }
+    }
+
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        self.keys().instruction(program_id, amount)
+    }
 }
 
-pub fn claim_reward<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, ClaimReward<'info>>,
+/// Redeems into a wrapped SOL account and closes it to `owner`, leaving native
+/// lamports. The transfer authority must also own the wrapped SOL destination.
+pub fn redeem_and_unwrap<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Redeem<'info>>,
+    owner: AccountInfo<'info>,
+    amount: u64,
 ) -> Result<()> {
-    let ix = port_claim_reward(
-        port_staking_id(),
-        ctx.accounts.stake_account_owner.key(),
-        ctx.accounts.stake_account.key(),
-        ctx.accounts.staking_pool.key(),
-        ctx.accounts.reward_token_pool.key(),
-        ctx.accounts.reward_dest.key(),
-    );
-
+    if !port_accessor::reserve_is_native(&ctx.accounts.reserve)? {
+        msg!("Redeem and unwrap requires a native mint reserve");
+        return Err(error!(PortAdaptorError::NotNativeMint));
+    }
+    let destination = ctx.accounts.destination_liquidity.clone();
+    let authority = ctx.accounts.transfer_authority.clone();
+    let token_program = ctx.accounts.token_program.clone();
+    let signer_seeds = ctx.signer_seeds;
+    redeem(ctx, amount)?;
+
+    let ix = spl_token::instruction::close_account(
+        &spl_token::id(),
+        destination.key,
+        owner.key,
+        authority.key,
+        &[],
+    )?;
+    log_cpi(&ix);
     invoke_signed(
         &ix,
-        &[
-            ctx.accounts.stake_account_owner,
-            ctx.accounts.stake_account,
-            ctx.accounts.staking_pool,
-            ctx.accounts.reward_token_pool,
-            ctx.accounts.reward_dest,
-            ctx.accounts.staking_program_authority,
-            ctx.accounts.clock,
-            ctx.accounts.token_program,
-            ctx.program,
-        ],
-        ctx.signer_seeds,
+        &[destination, owner, authority, token_program],
+        signer_seeds,
     )
     .map_err(Into::into)
 }
 
+/// Repays the obligation's borrow on a reserve using collateral deposited in
+/// that same reserve; redeeming another reserve's collateral yields a different
+/// token, which would need a swap before it could repay the debt.
+///
+/// The withdraw destination must be the redeem source, and the redeem
+/// destination the repay source. The collateral withdrawn is rounded up from
+/// the debt, so the redeemed liquidity can exceed the debt by a few lamports;
+/// that excess is left in the redeem destination.
+///
+/// Port checks the withdraw against the obligation's LTV while the debt is
+/// still outstanding, so the withdraw is capped at
+/// `PortObligation::max_withdraw_collateral` and only part of the debt may be
+/// repaid. Each call frees borrowing power for the next, so a leveraged
+/// obligation unwinds over several calls. The cap uses the obligation's
+/// refreshed values, so refresh it earlier in the same transaction. Returns the
+/// amount repaid.
+pub fn repay_with_same_reserve_collateral<'info>(
+    withdraw_ctx: CpiContext<'_, '_, '_, 'info, Withdraw<'info>>,
+    redeem_ctx: CpiContext<'_, '_, '_, 'info, Redeem<'info>>,
+    repay_ctx: CpiContext<'_, '_, '_, 'info, Repay<'info>>,
+) -> Result<u64> {
+    let reserve_key = repay_ctx.accounts.reserve.key();
+    if withdraw_ctx.accounts.reserve.key() != reserve_key
+        || redeem_ctx.accounts.reserve.key() != reserve_key
+    {
+        return Err(error!(PortAdaptorError::CollateralReserveMismatch));
+    }
+    if withdraw_ctx.accounts.destination_collateral.key()
+        != redeem_ctx.accounts.source_collateral.key()
+    {
+        msg!(
+            "Withdraw destination {} is not the redeem source {}",
+            withdraw_ctx.accounts.destination_collateral.key(),
+            redeem_ctx.accounts.source_collateral.key()
+        );
+        return Err(error!(PortAdaptorError::ChainedAccountMismatch));
+    }
+    if redeem_ctx.accounts.destination_liquidity.key() != repay_ctx.accounts.source_liquidity.key()
+    {
+        msg!(
+            "Redeem destination {} is not the repay source {}",
+            redeem_ctx.accounts.destination_liquidity.key(),
+            repay_ctx.accounts.source_liquidity.key()
+        );
+        return Err(error!(PortAdaptorError::ChainedAccountMismatch));
+    }
+    let obligation = {
+        let data = repay_ctx.accounts.obligation.try_borrow_data()?;
+        PortObligation::try_deserialize(&mut &data[..])?
+    };
+    let debt = obligation
+        .borrows
+        .iter()
+        .find(|b| b.borrow_reserve == reserve_key)
+        .map_or(Ok(0), |b| b.borrowed_amount_wads.try_ceil_u64())?;
+    if debt == 0 {
+        return Ok(0);
+    }
+    let collateral_amount = {
+        let data = repay_ctx.accounts.reserve.try_borrow_data()?;
+        let reserve = PortReserve::try_deserialize(&mut &data[..])?;
+        reserve
+            .collateral_exchange_rate()?
+            .decimal_liquidity_to_collateral(Decimal::from(debt))?
+            .try_ceil_u64()?
+            .min(obligation.max_withdraw_collateral(&reserve_key, &reserve)?)
+    };
+    if collateral_amount == 0 {
+        return Ok(0);
+    }
+
+    withdraw(withdraw_ctx, collateral_amount)?;
+    let destination = redeem_ctx.accounts.destination_liquidity.clone();
+    let balance_before = token_account_balance(&destination)?;
+    redeem(redeem_ctx, collateral_amount)?;
+    let redeemed = token_account_balance(&destination)?.saturating_sub(balance_before);
+
+    let amount = debt.min(redeemed);
+    repay(repay_ctx, amount)?;
+    Ok(amount)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OracleType {
+    None,
+    Pyth,
+    Switchboard,
+}
+
+impl OracleType {
+    pub fn accounts_len(&self) -> usize {
+        match self {
+            OracleType::None => 0,
+            OracleType::Pyth | OracleType::Switchboard => 1,
+        }
+    }
+}
+
+pub fn refresh_port_reserve<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, RefreshReserve<'info>>,
+    oracle_type: OracleType,
+) -> Result<()> {
+    check_clock(&ctx.accounts.clock)?;
+    assert_owned_by(&ctx.accounts.reserve, ctx.program.key)?;
+    let oracle_len = oracle_type.accounts_len();
+    if ctx.remaining_accounts.len() < oracle_len {
+        return Err(error!(anchor_lang::error::ErrorCode::AccountNotEnoughKeys));
+    }
+    let oracle = &ctx.remaining_accounts[..oracle_len];
+    if let (Some(expected), Some(passed)) = (
+        port_accessor::reserve_oracle_pubkey_opt(&ctx.accounts.reserve)?,
+        oracle.first(),
+    ) {
+        if expected != passed.key() {
+            msg!("Expected oracle {}, got {}", expected, passed.key());
+            return Err(error!(PortAdaptorError::OracleMismatch));
+        }
+    }
+    let ix = refresh_reserve(
+        ctx.program.key(),
+        ctx.accounts.reserve.key(),
+        oracle
+            .first()
+            .map_or(COption::None, |k| COption::Some(k.key())),
+    );
+    let mut accounts = vec![ctx.accounts.reserve, ctx.accounts.clock, ctx.program];
+    accounts.extend_from_slice(oracle);
+    log_cpi(&ix);
+    invoke(&ix, &accounts).map_err(Into::into)
+}
+
+pub fn refresh_and_exchange_rate<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, RefreshReserve<'info>>,
+    oracle_type: OracleType,
+) -> Result<CollateralExchangeRate> {
+    let reserve = ctx.accounts.reserve.clone();
+    refresh_port_reserve(ctx, oracle_type)?;
+    let data = reserve.try_borrow_data()?;
+    PortReserve::try_deserialize(&mut &data[..])?
+        .collateral_exchange_rate()
+        .map_err(Into::into)
+}
+
+#[derive(Accounts)]
+pub struct RefreshReserve<'info> {
+    pub reserve: AccountInfo<'info>,
+    pub clock: AccountInfo<'info>,
+}
+
+pub fn refresh_reserve_account_metas(
+    program_id: Pubkey,
+    reserve: Pubkey,
+    oracle: Option<Pubkey>,
+) -> Vec<AccountMeta> {
+    refresh_reserve(
+        program_id,
+        reserve,
+        oracle.map_or(COption::None, COption::Some),
+    )
+    .accounts
+}
+
+pub fn refresh_port_obligation<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, RefreshObligation<'info>>,
+) -> Result<()> {
+    check_clock(&ctx.accounts.clock)?;
+    assert_owned_by(&ctx.accounts.obligation, ctx.program.key)?;
+    let reserves = ctx.remaining_accounts;
+    for reserve in reserves.iter() {
+        assert_owned_by(reserve, ctx.program.key)?;
+    }
+    let ix = refresh_obligation(
+        ctx.program.key(),
+        ctx.accounts.obligation.key(),
+        reserves.iter().map(|info| info.key()).collect(),
+    );
+    let mut account_infos = vec![ctx.accounts.obligation, ctx.accounts.clock];
+    account_infos.extend(reserves);
+    account_infos.push(ctx.program);
+    log_cpi(&ix);
+    invoke(&ix, &account_infos).map_err(Into::into)
+}
+
+/// Like `refresh_port_obligation`, but first requires every forwarded reserve
+/// to have been refreshed in the current slot.
+pub fn refresh_port_obligation_strict<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, RefreshObligation<'info>>,
+) -> Result<()> {
+    check_clock(&ctx.accounts.clock)?;
+    let current_slot = Clock::from_account_info(&ctx.accounts.clock)?.slot;
+    assert_reserves_fresh(&ctx.remaining_accounts, current_slot)?;
+    refresh_port_obligation(ctx)
+}
+
+/// Like `refresh_port_obligation`, but first requires the forwarded reserves
+/// to be exactly the obligation's deposit then borrow reserves, in order.
+pub fn refresh_port_obligation_validated<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, RefreshObligation<'info>>,
+) -> Result<()> {
+    let expected = port_accessor::obligation_reserves(&ctx.accounts.obligation)?;
+    if !expected
+        .iter()
+        .copied()
+        .eq(ctx.remaining_accounts.iter().map(|info| info.key()))
+    {
+        msg!(
+            "Obligation {} expects reserves {:?}",
+            ctx.accounts.obligation.key(),
+            expected
+        );
+        return Err(error!(PortAdaptorError::ReserveSetMismatch));
+    }
+    refresh_port_obligation(ctx)
+}
+
+#[derive(Accounts)]
+pub struct RefreshObligation<'info> {
+    pub obligation: AccountInfo<'info>,
+    pub clock: AccountInfo<'info>,
+}
+
+pub fn refresh_obligation_account_metas(
+    program_id: Pubkey,
+    obligation: Pubkey,
+    reserves: &[Pubkey],
+) -> Vec<AccountMeta> {
+    refresh_obligation(program_id, obligation, reserves.to_vec()).accounts
+}
+
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
+
+pub fn estimate_refresh_accounts_len(reserve_count: usize) -> usize {
+    // obligation, clock and one account per reserve
+    2 + reserve_count
+}
+
+pub fn refresh_obligation_fits(reserve_count: usize) -> bool {
+    let accounts_len = estimate_refresh_accounts_len(reserve_count);
+    // fee payer and the lending program are needed on top of the instruction accounts
+    let keys_len = accounts_len + 2;
+    let size = 1 + 64 // signature
+        + 3 // message header
+        + 1 + keys_len * 32
+        + 32 // recent blockhash
+        + 1 // instruction count
+        + 1 // program id index
+        + 1 + accounts_len
+        + 1 + 1; // instruction data
+    size <= MAX_TRANSACTION_SIZE
+}
+
+pub mod compute_budget {
+    use anchor_lang::prelude::*;
+
+    declare_id!("ComputeBudget111111111111111111111111111111");
+}
+
+/// Clients batching several adaptor instructions (e.g. reserve refreshes)
+/// should put this first in the transaction to raise the compute limit.
+pub fn compute_budget_ix(units: u32) -> Instruction {
+    // ComputeBudgetInstruction::SetComputeUnitLimit
+    let mut data = vec![2u8];
+    data.extend_from_slice(&units.to_le_bytes());
+    Instruction {
+        program_id: compute_budget::id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+pub fn derive_reward_dest(owner: &Pubkey, reward_mint: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address(owner, reward_mint)
+}
+
+// Rough per-CPI compute unit costs including the Port program's own logging.
+// They are not measured guarantees; add headroom before passing the total to
+// `compute_budget_ix`.
+pub const INIT_OBLIGATION_CU: u32 = 10_000;
+pub const DEPOSIT_CU: u32 = 30_000;
+pub const DEPOSIT_AND_COLLATERALIZE_CU: u32 = 70_000;
+pub const BORROW_CU: u32 = 45_000;
+pub const REPAY_CU: u32 = 30_000;
+pub const WITHDRAW_CU: u32 = 60_000;
+pub const REDEEM_CU: u32 = 30_000;
+pub const REFRESH_RESERVE_CU: u32 = 15_000;
+pub const REFRESH_OBLIGATION_CU: u32 = 10_000;
+pub const REFRESH_OBLIGATION_PER_RESERVE_CU: u32 = 5_000;
+pub const CLAIM_REWARD_CU: u32 = 25_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CpiKind {
+    InitObligation,
+    Deposit,
+    DepositAndCollateralize,
+    Borrow,
+    Repay,
+    Withdraw,
+    Redeem,
+    RefreshReserve,
+    RefreshObligation { reserve_count: u32 },
+    ClaimReward,
+}
+
+pub fn estimate_cpi_units(kind: CpiKind) -> u32 {
+    match kind {
+        CpiKind::InitObligation => INIT_OBLIGATION_CU,
+        CpiKind::Deposit => DEPOSIT_CU,
+        CpiKind::DepositAndCollateralize => DEPOSIT_AND_COLLATERALIZE_CU,
+        CpiKind::Borrow => BORROW_CU,
+        CpiKind::Repay => REPAY_CU,
+        CpiKind::Withdraw => WITHDRAW_CU,
+        CpiKind::Redeem => REDEEM_CU,
+        CpiKind::RefreshReserve => REFRESH_RESERVE_CU,
+        CpiKind::RefreshObligation { reserve_count } => REFRESH_OBLIGATION_CU
+            .saturating_add(REFRESH_OBLIGATION_PER_RESERVE_CU.saturating_mul(reserve_count)),
+        CpiKind::ClaimReward => CLAIM_REWARD_CU,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortAccountKind {
+    LendingMarket,
+    Reserve,
+    Obligation,
+    StakingPool,
+    StakeAccount,
+}
+
+pub fn rent_exempt_lamports(rent: Rent, kind: PortAccountKind) -> u64 {
+    let len = match kind {
+        PortAccountKind::LendingMarket => PortLendingMarket::LEN,
+        PortAccountKind::Reserve => PortReserve::LEN,
+        PortAccountKind::Obligation => PortObligation::LEN,
+        PortAccountKind::StakingPool => PortStakingPool::LEN,
+        PortAccountKind::StakeAccount => PortStakeAccount::LEN,
+    };
+    rent.minimum_balance(len)
+}
+
+pub fn claim_reward_instruction(
+    program_id: Pubkey,
+    stake_account_owner: Pubkey,
+    stake_account: Pubkey,
+    staking_pool: Pubkey,
+    reward_token_pool: Pubkey,
+    reward_dest: Pubkey,
+    sub_reward: Option<(Pubkey, Pubkey)>,
+) -> Instruction {
+    let mut ix = port_claim_reward(
+        program_id,
+        stake_account_owner,
+        stake_account,
+        staking_pool,
+        reward_token_pool,
+        reward_dest,
+    );
+    if let Some((sub_reward_token_pool, sub_reward_dest)) = sub_reward {
+        ix.accounts
+            .push(AccountMeta::new(sub_reward_token_pool, false));
+        ix.accounts.push(AccountMeta::new(sub_reward_dest, false));
+    }
+    ix
+}
+
+pub fn claim_reward<'info>(ctx: CpiContext<'_, '_, '_, 'info, ClaimReward<'info>>) -> Result<()> {
+    check_clock(&ctx.accounts.clock)?;
+    let sub_reward = match ctx.remaining_accounts.as_slice() {
+        [sub_reward_token_pool, sub_reward_dest, ..] => {
+            Some((sub_reward_token_pool.key(), sub_reward_dest.key()))
+        }
+        _ => None,
+    };
+    let ix = ctx.accounts.instruction(ctx.program.key(), sub_reward);
+
+    let mut accounts = vec![
+        ctx.accounts.stake_account_owner,
+        ctx.accounts.stake_account,
+        ctx.accounts.staking_pool,
+        ctx.accounts.reward_token_pool,
+        ctx.accounts.reward_dest,
+        ctx.accounts.staking_program_authority,
+        ctx.accounts.clock,
+        ctx.accounts.token_program,
+        ctx.program,
+    ];
+    accounts.extend(ctx.remaining_accounts.into_iter().take(2));
+    log_cpi(&ix);
+    invoke_signed(&ix, &accounts, ctx.signer_seeds).map_err(Into::into)
+}
+
+// Each claim references up to ten accounts, so more than this many pools will
+// not fit in one transaction alongside the caller's own accounts.
+pub const MAX_CLAIM_REWARDS_BATCH: usize = 4;
+
+pub fn claim_rewards_batch<'info>(
+    ctxs: Vec<CpiContext<'_, '_, '_, 'info, ClaimReward<'info>>>,
+) -> Result<()> {
+    if ctxs.len() > MAX_CLAIM_REWARDS_BATCH {
+        msg!(
+            "Cannot claim from {} pools, at most {} per transaction",
+            ctxs.len(),
+            MAX_CLAIM_REWARDS_BATCH
+        );
+        return Err(error!(PortAdaptorError::TooManyClaims));
+    }
+    ctxs.into_iter().try_for_each(claim_reward)
+}
+
 #[derive(Accounts, Clone)]
 pub struct ClaimReward<'info> {
     pub stake_account_owner: AccountInfo<'info>,
@@ -474,26 +1565,87 @@ pub struct ClaimReward<'info> {
     pub token_program: AccountInfo<'info>,
 }
 
-pub fn create_port_staking_pool<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, CreateStakingPoolContext<'info>>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClaimRewardKeys {
+    pub stake_account_owner: Pubkey,
+    pub stake_account: Pubkey,
+    pub staking_pool: Pubkey,
+    pub reward_token_pool: Pubkey,
+    pub reward_dest: Pubkey,
+}
+
+impl ClaimRewardKeys {
+    fn instruction(&self, program_id: Pubkey, sub_reward: Option<(Pubkey, Pubkey)>) -> Instruction {
+        claim_reward_instruction(
+            program_id,
+            self.stake_account_owner,
+            self.stake_account,
+            self.staking_pool,
+            self.reward_token_pool,
+            self.reward_dest,
+            sub_reward,
+        )
+    }
+}
+
+pub fn claim_reward_account_metas(
+    program_id: Pubkey,
+    keys: &ClaimRewardKeys,
+    sub_reward: Option<(Pubkey, Pubkey)>,
+) -> Vec<AccountMeta> {
+    keys.instruction(program_id, sub_reward).accounts
+}
+
+impl<'info> ClaimReward<'info> {
+    pub fn keys(&self) -> ClaimRewardKeys {
+        ClaimRewardKeys {
+            stake_account_owner: self.stake_account_owner.key(),
+            stake_account: self.stake_account.key(),
+            staking_pool: self.staking_pool.key(),
+            reward_token_pool: self.reward_token_pool.key(),
+            reward_dest: self.reward_dest.key(),
+        }
+    }
+
+    pub fn instruction(
+        &self,
+        program_id: Pubkey,
+        sub_reward: Option<(Pubkey, Pubkey)>,
+    ) -> Instruction {
+        self.keys().instruction(program_id, sub_reward)
+    }
+}
+
+/// A number of slots, as opposed to an absolute `Slot`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SlotDuration(pub u64);
+
+impl SlotDuration {
+    pub fn slots(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for SlotDuration {
+    fn from(slots: u64) -> Self {
+        SlotDuration(slots)
+    }
+}
+
+pub fn create_port_staking_pool<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, CreateStakingPoolContext<'info>>,
     supply: u64,
-    duration: u64,
+    duration: SlotDuration,
     earliest_reward_claim_time: Slot,
 ) -> Result<()> {
-    let ix = init_port_staking_pool(
-        port_staking_id(),
+    let ix = ctx.accounts.instruction(
+        ctx.program.key(),
         supply,
         duration,
         earliest_reward_claim_time,
-        ctx.accounts.transfer_authority.key(),
-        ctx.accounts.reward_token_supply.key(),
-        ctx.accounts.reward_token_pool.key(),
-        ctx.accounts.staking_pool.key(),
-        ctx.accounts.reward_token_mint.key(),
-        ctx.accounts.staking_pool_owner.key(),
-        ctx.accounts.admin.key(),
     );
 
+    log_cpi(&ix);
     invoke_signed(
         &ix,
         &[
@@ -512,6 +1664,21 @@ pub fn create_port_staking_pool<'a, 'b, 'c, 'info>(
     .map_err(Into::into)
 }
 
+/// The staking program derives one authority per pool; it owns the reward
+/// token pool and signs reward payouts. Pass it as `staking_program_derived`.
+pub fn staking_program_authority(staking_pool: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[staking_pool.as_ref()], program_id)
+}
+
+/// Before creating a pool:
+/// - `reward_token_supply` is an initialized token account of
+///   `reward_token_mint` holding at least `supply`, owned by
+///   `transfer_authority`;
+/// - `reward_token_pool` is a rent-exempt, uninitialized token-sized account
+///   owned by the token program, which the staking program initializes with
+///   `staking_program_authority` as owner;
+/// - `staking_pool` is allocated with `PortStakingPool::LEN` bytes and owned by
+///   the staking program.
 #[derive(Accounts, Clone)]
 pub struct CreateStakingPoolContext<'info> {
     pub staking_pool: AccountInfo<'info>,
@@ -526,15 +1693,79 @@ pub struct CreateStakingPoolContext<'info> {
     pub rent: AccountInfo<'info>,
 }
 
-pub fn create_stake_account<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, CreateStakeAccount<'info>>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CreateStakingPoolKeys {
+    pub transfer_authority: Pubkey,
+    pub reward_token_supply: Pubkey,
+    pub reward_token_pool: Pubkey,
+    pub staking_pool: Pubkey,
+    pub reward_token_mint: Pubkey,
+    pub staking_pool_owner: Pubkey,
+    pub admin: Pubkey,
+}
+
+impl CreateStakingPoolKeys {
+    fn instruction(
+        &self,
+        program_id: Pubkey,
+        supply: u64,
+        duration: SlotDuration,
+        earliest_reward_claim_time: Slot,
+    ) -> Instruction {
+        init_port_staking_pool(
+            program_id,
+            supply,
+            duration.slots(),
+            earliest_reward_claim_time,
+            self.transfer_authority,
+            self.reward_token_supply,
+            self.reward_token_pool,
+            self.staking_pool,
+            self.reward_token_mint,
+            self.staking_pool_owner,
+            self.admin,
+        )
+    }
+}
+
+pub fn create_staking_pool_account_metas(
+    program_id: Pubkey,
+    keys: &CreateStakingPoolKeys,
+) -> Vec<AccountMeta> {
+    keys.instruction(program_id, 0, SlotDuration::default(), 0)
+        .accounts
+}
+
+impl<'info> CreateStakingPoolContext<'info> {
+    pub fn keys(&self) -> CreateStakingPoolKeys {
+        CreateStakingPoolKeys {
+            transfer_authority: self.transfer_authority.key(),
+            reward_token_supply: self.reward_token_supply.key(),
+            reward_token_pool: self.reward_token_pool.key(),
+            staking_pool: self.staking_pool.key(),
+            reward_token_mint: self.reward_token_mint.key(),
+            staking_pool_owner: self.staking_pool_owner.key(),
+            admin: self.admin.key(),
+        }
+    }
+
+    fn instruction(
+        &self,
+        program_id: Pubkey,
+        supply: u64,
+        duration: SlotDuration,
+        earliest_reward_claim_time: Slot,
+    ) -> Instruction {
+        self.keys()
+            .instruction(program_id, supply, duration, earliest_reward_claim_time)
+    }
+}
+
+pub fn create_stake_account<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, CreateStakeAccount<'info>>,
 ) -> Result<()> {
-    let ix = create_port_stake_account(
-        port_staking_id(),
-        ctx.accounts.stake_account.key(),
-        ctx.accounts.staking_pool.key(),
-        ctx.accounts.owner.key(),
-    );
+    let ix = ctx.accounts.instruction(ctx.program.key());
+    log_cpi(&ix);
     invoke_signed(
         &ix,
         &[
@@ -549,6 +1780,22 @@ pub fn create_stake_account<'a, 'b, 'c, 'info>(
     .map_err(Into::into)
 }
 
+/// Stake accounts are allocated zeroed and owned by the staking program before
+/// `create_stake_account` initializes them, so any non-zero byte means the
+/// account is already set up.
+pub fn stake_account_exists(info: &AccountInfo, program_id: &Pubkey) -> Result<bool> {
+    Ok(info.owner == program_id && info.try_borrow_data()?.iter().any(|b| *b != 0))
+}
+
+pub fn create_stake_account_idempotent<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, CreateStakeAccount<'info>>,
+) -> Result<()> {
+    if stake_account_exists(&ctx.accounts.stake_account, ctx.program.key)? {
+        return Ok(());
+    }
+    create_stake_account(ctx)
+}
+
 #[derive(Accounts, Clone)]
 pub struct CreateStakeAccount<'info> {
     pub staking_pool: AccountInfo<'info>,
@@ -557,17 +1804,52 @@ pub struct CreateStakeAccount<'info> {
     pub rent: AccountInfo<'info>,
 }
 
-pub fn port_stake<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, PortStake<'info>>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CreateStakeAccountKeys {
+    pub stake_account: Pubkey,
+    pub staking_pool: Pubkey,
+    pub owner: Pubkey,
+}
+
+impl CreateStakeAccountKeys {
+    fn instruction(&self, program_id: Pubkey) -> Instruction {
+        create_port_stake_account(
+            program_id,
+            self.stake_account,
+            self.staking_pool,
+            self.owner,
+        )
+    }
+}
+
+pub fn create_stake_account_metas(
+    program_id: Pubkey,
+    keys: &CreateStakeAccountKeys,
+) -> Vec<AccountMeta> {
+    keys.instruction(program_id).accounts
+}
+
+impl<'info> CreateStakeAccount<'info> {
+    pub fn keys(&self) -> CreateStakeAccountKeys {
+        CreateStakeAccountKeys {
+            stake_account: self.stake_account.key(),
+            staking_pool: self.staking_pool.key(),
+            owner: self.owner.key(),
+        }
+    }
+
+    fn instruction(&self, program_id: Pubkey) -> Instruction {
+        self.keys().instruction(program_id)
+    }
+}
+
+pub fn port_stake<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, PortStake<'info>>,
     amount: u64,
 ) -> Result<()> {
-    let ix = port_staking_deposit(
-        port_staking_id(),
-        amount,
-        ctx.accounts.authority.key(),
-        ctx.accounts.stake_account.key(),
-        ctx.accounts.staking_pool.key(),
-    );
+    check_clock(&ctx.accounts.clock)?;
+    let ix = ctx.accounts.instruction(ctx.program.key(), amount);
+    log_cpi(&ix);
     invoke_signed(
         &ix,
         &[
@@ -590,17 +1872,60 @@ pub struct PortStake<'info> {
     pub clock: AccountInfo<'info>,
 }
 
-pub fn port_unstake<'a, 'b, 'c, 'info>(
-    ctx: CpiContext<'a, 'b, 'c, 'info, PortUnstake<'info>>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PortStakeKeys {
+    pub authority: Pubkey,
+    pub stake_account: Pubkey,
+    pub staking_pool: Pubkey,
+}
+
+impl PortStakeKeys {
+    fn stake_instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        port_staking_deposit(
+            program_id,
+            amount,
+            self.authority,
+            self.stake_account,
+            self.staking_pool,
+        )
+    }
+
+    fn unstake_instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        port_staking_withdraw(
+            program_id,
+            amount,
+            self.authority,
+            self.stake_account,
+            self.staking_pool,
+        )
+    }
+}
+
+pub fn port_stake_account_metas(program_id: Pubkey, keys: &PortStakeKeys) -> Vec<AccountMeta> {
+    keys.stake_instruction(program_id, 0).accounts
+}
+
+impl<'info> PortStake<'info> {
+    pub fn keys(&self) -> PortStakeKeys {
+        PortStakeKeys {
+            authority: self.authority.key(),
+            stake_account: self.stake_account.key(),
+            staking_pool: self.staking_pool.key(),
+        }
+    }
+
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        self.keys().stake_instruction(program_id, amount)
+    }
+}
+
+pub fn port_unstake<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, PortUnstake<'info>>,
     amount: u64,
 ) -> Result<()> {
-    let ix = port_staking_withdraw(
-        port_staking_id(),
-        amount,
-        ctx.accounts.authority.key(),
-        ctx.accounts.stake_account.key(),
-        ctx.accounts.staking_pool.key(),
-    );
+    check_clock(&ctx.accounts.clock)?;
+    let ix = ctx.accounts.instruction(ctx.program.key(), amount);
+    log_cpi(&ix);
     invoke_signed(
         &ix,
         &[
@@ -623,6 +1948,173 @@ pub struct PortUnstake<'info> {
     pub clock: AccountInfo<'info>,
 }
 
+pub fn port_unstake_account_metas(program_id: Pubkey, keys: &PortStakeKeys) -> Vec<AccountMeta> {
+    keys.unstake_instruction(program_id, 0).accounts
+}
+
+impl<'info> PortUnstake<'info> {
+    pub fn keys(&self) -> PortStakeKeys {
+        PortStakeKeys {
+            authority: self.authority.key(),
+            stake_account: self.stake_account.key(),
+            staking_pool: self.staking_pool.key(),
+        }
+    }
+
+    fn instruction(&self, program_id: Pubkey, amount: u64) -> Instruction {
+        self.keys().unstake_instruction(program_id, amount)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PortSeeds {
+    seeds: Vec<Vec<u8>>,
+}
+
+impl PortSeeds {
+    pub fn new(seeds: Vec<Vec<u8>>, bump: u8) -> Self {
+        let mut seeds = seeds;
+        seeds.push(vec![bump]);
+        PortSeeds { seeds }
+    }
+
+    pub fn seeds(&self) -> Vec<&[u8]> {
+        self.seeds.iter().map(Vec::as_slice).collect()
+    }
+
+    /// Lends the seeds out in the `&[&[&[u8]]]` shape expected by
+    /// `CpiContext::new_with_signer` and `PortCpi::new`.
+    pub fn with_signer<R>(&self, f: impl FnOnce(&[&[&[u8]]]) -> R) -> R {
+        let seeds = self.seeds();
+        f(&[seeds.as_slice()])
+    }
+}
+
+pub fn lending_market_authority(lending_market: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[lending_market.as_ref()], program_id)
+}
+
+/// Seeds of the lending market authority PDA. Only the Port lending program can
+/// sign with them; other programs can use them to re-derive or verify the
+/// authority address, not to sign transfers on its behalf.
+pub fn lending_market_authority_signer_seeds(
+    lending_market: &Pubkey,
+    program_id: &Pubkey,
+) -> (PortSeeds, u8) {
+    let (_, bump) = lending_market_authority(lending_market, program_id);
+    (
+        PortSeeds::new(vec![lending_market.to_bytes().to_vec()], bump),
+        bump,
+    )
+}
+
+#[derive(Clone)]
+pub struct PortCpi<'a, 'b, 'c, 'info> {
+    pub lending_program: AccountInfo<'info>,
+    pub staking_program: AccountInfo<'info>,
+    pub signer_seeds: &'a [&'b [&'c [u8]]],
+}
+
+impl<'a, 'b, 'c, 'info> PortCpi<'a, 'b, 'c, 'info> {
+    pub fn new(
+        lending_program: AccountInfo<'info>,
+        staking_program: AccountInfo<'info>,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> Self {
+        PortCpi {
+            lending_program,
+            staking_program,
+            signer_seeds,
+        }
+    }
+
+    fn lending<T: ToAccountMetas + ToAccountInfos<'info>>(
+        &self,
+        accounts: T,
+    ) -> CpiContext<'a, 'b, 'c, 'info, T> {
+        CpiContext::new_with_signer(self.lending_program.clone(), accounts, self.signer_seeds)
+    }
+
+    fn staking<T: ToAccountMetas + ToAccountInfos<'info>>(
+        &self,
+        accounts: T,
+    ) -> CpiContext<'a, 'b, 'c, 'info, T> {
+        CpiContext::new_with_signer(self.staking_program.clone(), accounts, self.signer_seeds)
+    }
+
+    pub fn init_obligation(&self, accounts: InitObligation<'info>) -> Result<()> {
+        init_obligation(self.lending(accounts))
+    }
+
+    pub fn deposit(&self, accounts: Deposit<'info>, amount: u64) -> Result<()> {
+        deposit_reserve(self.lending(accounts), amount)
+    }
+
+    pub fn deposit_and_collateralize(
+        &self,
+        accounts: DepositAndCollateralize<'info>,
+        amount: u64,
+    ) -> Result<()> {
+        deposit_and_collateralize(self.lending(accounts), amount)
+    }
+
+    pub fn borrow(&self, accounts: Borrow<'info>, amount: u64) -> Result<()> {
+        borrow(self.lending(accounts), amount)
+    }
+
+    pub fn repay(&self, accounts: Repay<'info>, amount: u64) -> Result<()> {
+        repay(self.lending(accounts), amount)
+    }
+
+    pub fn repay_max(&self, accounts: Repay<'info>) -> Result<u64> {
+        repay_max(self.lending(accounts))
+    }
+
+    pub fn withdraw(&self, accounts: Withdraw<'info>, amount: u64) -> Result<()> {
+        withdraw(self.lending(accounts), amount)
+    }
+
+    pub fn redeem(&self, accounts: Redeem<'info>, amount: u64) -> Result<()> {
+        redeem(self.lending(accounts), amount)
+    }
+
+    pub fn refresh_reserve(
+        &self,
+        accounts: RefreshReserve<'info>,
+        oracle_type: OracleType,
+        oracle: Vec<AccountInfo<'info>>,
+    ) -> Result<()> {
+        refresh_port_reserve(
+            self.lending(accounts).with_remaining_accounts(oracle),
+            oracle_type,
+        )
+    }
+
+    pub fn refresh_obligation(
+        &self,
+        accounts: RefreshObligation<'info>,
+        reserves: Vec<AccountInfo<'info>>,
+    ) -> Result<()> {
+        refresh_port_obligation(self.lending(accounts).with_remaining_accounts(reserves))
+    }
+
+    pub fn claim_reward(&self, accounts: ClaimReward<'info>) -> Result<()> {
+        claim_reward(self.staking(accounts))
+    }
+
+    pub fn create_stake_account(&self, accounts: CreateStakeAccount<'info>) -> Result<()> {
+        create_stake_account(self.staking(accounts))
+    }
+
+    pub fn stake(&self, accounts: PortStake<'info>, amount: u64) -> Result<()> {
+        port_stake(self.staking(accounts), amount)
+    }
+
+    pub fn unstake(&self, accounts: PortUnstake<'info>, amount: u64) -> Result<()> {
+        port_unstake(self.staking(accounts), amount)
+    }
+}
+
 pub mod port_accessor {
     use std::convert::TryFrom;
 
@@ -633,49 +2125,126 @@ pub mod port_accessor {
         OBLIGATION_LIQUIDITY_LEN,
     };
 
-    use solana_maths::{Decimal, Rate, TryAdd, TryDiv, TrySub};
+    use solana_maths::{Decimal, Rate, TryAdd, TryDiv, TryMul, TrySub};
+
+    use crate::layout;
+    use crate::rates::{InterestRateCurve, SLOTS_PER_YEAR};
 
     use super::*;
 
-    fn unpack_decimal(src: &[u8; 16]) -> Decimal {
+    /// Decodes a little-endian WAD-scaled decimal as stored in Port accounts.
+    pub fn parse_decimal(src: &[u8; 16]) -> Decimal {
         Decimal::from_scaled_val(u128::from_le_bytes(*src))
     }
 
     pub fn reserve_ltv(account: &AccountInfo) -> std::result::Result<u8, Error> {
         let bytes = account.try_borrow_data()?;
-        let mut amount_bytes = [0u8; 1];
-        amount_bytes.copy_from_slice(&bytes[304..305]);
-        Ok(u8::from_le_bytes(amount_bytes))
+        Ok(bytes[layout::reserve::CONFIG_LOAN_TO_VALUE_RATIO])
     }
 
     pub fn reserve_available_liquidity(account: &AccountInfo) -> std::result::Result<u64, Error> {
         let bytes = account.try_borrow_data()?;
         let mut amount_bytes = [0u8; 8];
-        amount_bytes.copy_from_slice(&bytes[175..183]);
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::LIQUIDITY_AVAILABLE_AMOUNT]);
         Ok(u64::from_le_bytes(amount_bytes))
     }
 
     pub fn reserve_borrowed_amount(account: &AccountInfo) -> std::result::Result<Decimal, Error> {
         let bytes = account.try_borrow_data()?;
         let mut amount_bytes = [0u8; 16];
-        amount_bytes.copy_from_slice(&bytes[183..199]);
-        Ok(unpack_decimal(&amount_bytes))
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::LIQUIDITY_BORROWED_AMOUNT_WADS]);
+        Ok(parse_decimal(&amount_bytes))
+    }
+
+    pub fn reserve_borrowed_amount_u64(account: &AccountInfo) -> std::result::Result<u64, Error> {
+        reserve_borrowed_amount(account)?
+            .try_ceil_u64()
+            .map_err(Into::into)
+    }
+
+    pub fn reserve_borrow_rate_index(account: &AccountInfo) -> std::result::Result<Decimal, Error> {
+        let bytes = account.try_borrow_data()?;
+        let mut amount_bytes = [0u8; 16];
+        amount_bytes
+            .copy_from_slice(&bytes[layout::reserve::LIQUIDITY_CUMULATIVE_BORROW_RATE_WADS]);
+        Ok(parse_decimal(&amount_bytes))
     }
 
     pub fn reserve_market_price(account: &AccountInfo) -> std::result::Result<Decimal, Error> {
         let bytes = account.try_borrow_data()?;
         let mut amount_bytes = [0u8; 16];
-        amount_bytes.copy_from_slice(&bytes[215..231]);
-        Ok(unpack_decimal(&amount_bytes))
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::LIQUIDITY_MARKET_PRICE]);
+        Ok(parse_decimal(&amount_bytes))
     }
 
+    /// Assumes the reserve has an oracle; returns the default pubkey otherwise.
+    /// Use `reserve_oracle_pubkey_opt` when the oracle may be absent.
     pub fn reserve_oracle_pubkey(account: &AccountInfo) -> std::result::Result<Pubkey, Error> {
         let bytes = account.try_borrow_data()?;
         let mut amount_bytes = [0u8; 32];
-        amount_bytes.copy_from_slice(&bytes[143..175]);
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::LIQUIDITY_ORACLE]);
         Ok(Pubkey::new_from_array(amount_bytes))
     }
 
+    pub fn reserve_oracle_pubkey_opt(
+        account: &AccountInfo,
+    ) -> std::result::Result<Option<Pubkey>, Error> {
+        let bytes = account.try_borrow_data()?;
+        let mut tag_bytes = [0u8; 4];
+        tag_bytes.copy_from_slice(&bytes[layout::reserve::LIQUIDITY_ORACLE_TAG]);
+        if u32::from_le_bytes(tag_bytes) == 0 {
+            return Ok(None);
+        }
+        let mut amount_bytes = [0u8; 32];
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::LIQUIDITY_ORACLE]);
+        Ok(Some(Pubkey::new_from_array(amount_bytes)))
+    }
+
+    const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+    const PYTH_PRICE_PRODUCT: std::ops::Range<usize> = 112..144;
+
+    /// Port stores only the Pyth price account on a reserve. The product
+    /// account is read from the price account, which must be the reserve's
+    /// oracle. Returns `None` when the reserve has no oracle.
+    pub fn reserve_pyth_product(
+        account: &AccountInfo,
+        price_account: &AccountInfo,
+    ) -> std::result::Result<Option<Pubkey>, Error> {
+        let oracle = match reserve_oracle_pubkey_opt(account)? {
+            Some(oracle) => oracle,
+            None => return Ok(None),
+        };
+        if oracle != price_account.key() {
+            msg!("Expected oracle {}, got {}", oracle, price_account.key());
+            return Err(error!(PortAdaptorError::OracleMismatch));
+        }
+        let bytes = price_account.try_borrow_data()?;
+        if bytes.len() < PYTH_PRICE_PRODUCT.end {
+            return Err(ProgramError::AccountDataTooSmall.into());
+        }
+        let mut magic_bytes = [0u8; 4];
+        magic_bytes.copy_from_slice(&bytes[0..4]);
+        if u32::from_le_bytes(magic_bytes) != PYTH_MAGIC {
+            msg!("Oracle {} is not a Pyth price account", oracle);
+            return Err(ProgramError::InvalidAccountData.into());
+        }
+        Ok(Some(Pubkey::new(&bytes[PYTH_PRICE_PRODUCT])))
+    }
+
+    pub fn reserve_staking_pool(
+        account: &AccountInfo,
+    ) -> std::result::Result<Option<Pubkey>, Error> {
+        let bytes = account.try_borrow_data()?;
+        let mut tag_bytes = [0u8; 4];
+        tag_bytes.copy_from_slice(&bytes[layout::reserve::CONFIG_DEPOSIT_STAKING_POOL_TAG]);
+        if u32::from_le_bytes(tag_bytes) == 0 {
+            return Ok(None);
+        }
+        let mut amount_bytes = [0u8; 32];
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::CONFIG_DEPOSIT_STAKING_POOL]);
+        Ok(Some(Pubkey::new_from_array(amount_bytes)))
+    }
+
     pub fn reserve_total_liquidity(account: &AccountInfo) -> std::result::Result<Decimal, Error> {
         let available_liquidity = reserve_available_liquidity(account)?;
         let borrowed_amount = reserve_borrowed_amount(account)?;
@@ -689,35 +2258,125 @@ pub mod port_accessor {
     ) -> std::result::Result<Pubkey, Error> {
         let bytes = account.try_borrow_data()?;
         let mut amount_bytes = [0u8; 32];
-        amount_bytes.copy_from_slice(&bytes[42..74]);
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::LIQUIDITY_MINT]);
+        Ok(Pubkey::new_from_array(amount_bytes))
+    }
+
+    pub fn reserve_is_native(account: &AccountInfo) -> std::result::Result<bool, Error> {
+        Ok(reserve_liquidity_mint_pubkey(account)? == spl_token::native_mint::id())
+    }
+
+    /// The reserve's `liquidity.fee_receiver`, which `Borrow::reserve_fee_receiver`
+    /// must be. The host fee receiver is chosen per borrow and is not stored on
+    /// the reserve.
+    pub fn reserve_borrow_fee_receiver(
+        account: &AccountInfo,
+    ) -> std::result::Result<Pubkey, Error> {
+        let bytes = account.try_borrow_data()?;
+        let mut amount_bytes = [0u8; 32];
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::LIQUIDITY_FEE_RECEIVER]);
         Ok(Pubkey::new_from_array(amount_bytes))
     }
 
+    /// Port creates the collateral mint with the liquidity mint's decimals and
+    /// does not store them separately, so this reads the liquidity decimals.
+    pub fn reserve_collateral_mint_decimals(
+        account: &AccountInfo,
+    ) -> std::result::Result<u8, Error> {
+        let bytes = account.try_borrow_data()?;
+        bytes
+            .get(layout::reserve::LIQUIDITY_MINT_DECIMALS)
+            .copied()
+            .ok_or_else(|| ProgramError::AccountDataTooSmall.into())
+    }
+
     pub fn reserve_lp_mint_pubkey(account: &AccountInfo) -> std::result::Result<Pubkey, Error> {
         let bytes = account.try_borrow_data()?;
         let mut amount_bytes = [0u8; 32];
-        amount_bytes.copy_from_slice(&bytes[231..263]);
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::COLLATERAL_MINT]);
         Ok(Pubkey::new_from_array(amount_bytes))
     }
 
-    pub fn reserve_mint_total(account: &AccountInfo) -> std::result::Result<u64, Error> {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct ReserveLiquidityView {
+        pub mint_pubkey: Pubkey,
+        pub mint_decimals: u8,
+        pub supply_pubkey: Pubkey,
+        pub available_amount: u64,
+        pub borrowed_amount_wads: Decimal,
+        pub market_price: Decimal,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct ReserveCollateralView {
+        pub mint_pubkey: Pubkey,
+        pub mint_total_supply: u64,
+        pub supply_pubkey: Pubkey,
+    }
+
+    pub fn reserve_liquidity(
+        account: &AccountInfo,
+    ) -> std::result::Result<ReserveLiquidityView, Error> {
+        let bytes = account.try_borrow_data()?;
+        let mut amount_bytes = [0u8; 8];
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::LIQUIDITY_AVAILABLE_AMOUNT]);
+        let mut borrowed_bytes = [0u8; 16];
+        borrowed_bytes.copy_from_slice(&bytes[layout::reserve::LIQUIDITY_BORROWED_AMOUNT_WADS]);
+        let mut price_bytes = [0u8; 16];
+        price_bytes.copy_from_slice(&bytes[layout::reserve::LIQUIDITY_MARKET_PRICE]);
+        Ok(ReserveLiquidityView {
+            mint_pubkey: Pubkey::new(&bytes[layout::reserve::LIQUIDITY_MINT]),
+            mint_decimals: bytes[layout::reserve::LIQUIDITY_MINT_DECIMALS],
+            supply_pubkey: Pubkey::new(&bytes[layout::reserve::LIQUIDITY_SUPPLY]),
+            available_amount: u64::from_le_bytes(amount_bytes),
+            borrowed_amount_wads: parse_decimal(&borrowed_bytes),
+            market_price: parse_decimal(&price_bytes),
+        })
+    }
+
+    pub fn reserve_collateral(
+        account: &AccountInfo,
+    ) -> std::result::Result<ReserveCollateralView, Error> {
+        let bytes = account.try_borrow_data()?;
+        let mut amount_bytes = [0u8; 8];
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::COLLATERAL_MINT_TOTAL_SUPPLY]);
+        Ok(ReserveCollateralView {
+            mint_pubkey: Pubkey::new(&bytes[layout::reserve::COLLATERAL_MINT]),
+            mint_total_supply: u64::from_le_bytes(amount_bytes),
+            supply_pubkey: Pubkey::new(&bytes[layout::reserve::COLLATERAL_SUPPLY]),
+        })
+    }
+
+    pub fn reserve_collateral_mint_supply(
+        account: &AccountInfo,
+    ) -> std::result::Result<u64, Error> {
         let bytes = account.try_borrow_data()?;
         let mut amount_bytes = [0u8; 8];
-        amount_bytes.copy_from_slice(&bytes[263..271]);
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::COLLATERAL_MINT_TOTAL_SUPPLY]);
         Ok(u64::from_le_bytes(amount_bytes))
     }
 
+    #[deprecated(note = "use reserve_collateral_mint_supply instead")]
+    pub fn reserve_mint_total(account: &AccountInfo) -> std::result::Result<u64, Error> {
+        reserve_collateral_mint_supply(account)
+    }
+
     pub fn reserve_borrow_fee(account: &AccountInfo) -> std::result::Result<Rate, Error> {
         let bytes = account.try_borrow_data()?;
         let mut amount_bytes = [0u8; 8];
-        amount_bytes.copy_from_slice(&bytes[310..318]);
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::CONFIG_BORROW_FEE_WAD]);
         Ok(Rate::from_scaled_val(u64::from_le_bytes(amount_bytes)))
     }
 
+    pub fn reserve_host_fee_percentage(account: &AccountInfo) -> std::result::Result<u8, Error> {
+        let bytes = account.try_borrow_data()?;
+        Ok(bytes[layout::reserve::CONFIG_HOST_FEE_PERCENTAGE])
+    }
+
     pub fn exchange_rate(
         account: &AccountInfo,
     ) -> std::result::Result<CollateralExchangeRate, Error> {
-        let mint_total_supply = reserve_mint_total(account)?;
+        let mint_total_supply = reserve_collateral_mint_supply(account)?;
         let total_liquidity = reserve_total_liquidity(account)?;
         let rate = if mint_total_supply == 0 || total_liquidity == Decimal::zero() {
             Rate::from_scaled_val(INITIAL_COLLATERAL_RATE)
@@ -729,14 +2388,61 @@ pub mod port_accessor {
         Ok(CollateralExchangeRate(port_rate))
     }
 
+    pub fn reserve_current_borrow_rate(account: &AccountInfo) -> std::result::Result<Rate, Error> {
+        let total_liquidity = reserve_total_liquidity(account)?;
+        let utilization_rate = if total_liquidity == Decimal::zero() {
+            Rate::zero()
+        } else {
+            Rate::try_from(reserve_borrowed_amount(account)?.try_div(total_liquidity)?)?
+        };
+
+        reserve_interest_rate_curve(account)?.evaluate(utilization_rate)
+    }
+
+    pub fn reserve_interest_rate_curve(
+        account: &AccountInfo,
+    ) -> std::result::Result<InterestRateCurve, Error> {
+        let bytes = account.try_borrow_data()?;
+        Ok(InterestRateCurve {
+            optimal_utilization_rate: bytes[layout::reserve::CONFIG_OPTIMAL_UTILIZATION_RATE],
+            min_borrow_rate: bytes[layout::reserve::CONFIG_MIN_BORROW_RATE],
+            optimal_borrow_rate: bytes[layout::reserve::CONFIG_OPTIMAL_BORROW_RATE],
+            max_borrow_rate: bytes[layout::reserve::CONFIG_MAX_BORROW_RATE],
+        })
+    }
+
+    pub fn accrued_interest(
+        account: &AccountInfo,
+        principal_wads: Decimal,
+        from_slot: Slot,
+        to_slot: Slot,
+    ) -> std::result::Result<Decimal, Error> {
+        let slots_elapsed = to_slot.saturating_sub(from_slot);
+        let slot_interest_rate = reserve_current_borrow_rate(account)?.try_div(SLOTS_PER_YEAR)?;
+        let compounded_interest_rate = Rate::one()
+            .try_add(slot_interest_rate)?
+            .try_pow(slots_elapsed)?;
+        principal_wads
+            .try_mul(compounded_interest_rate)?
+            .try_sub(principal_wads)
+            .map_err(Into::into)
+    }
+
+    pub fn obligation_owner(account: &AccountInfo) -> std::result::Result<Pubkey, Error> {
+        let bytes = account.try_borrow_data()?;
+        let mut amount_bytes = [0u8; 32];
+        amount_bytes.copy_from_slice(&bytes[layout::obligation::OWNER]);
+        Ok(Pubkey::new_from_array(amount_bytes))
+    }
+
     pub fn obligation_deposits_count(account: &AccountInfo) -> std::result::Result<u8, Error> {
         let bytes = account.try_borrow_data()?;
-        Ok(bytes[138])
+        Ok(bytes[layout::obligation::DEPOSITS_LEN])
     }
 
     pub fn obligation_borrows_count(account: &AccountInfo) -> std::result::Result<u8, Error> {
         let bytes = account.try_borrow_data()?;
-        Ok(bytes[139])
+        Ok(bytes[layout::obligation::BORROWS_LEN])
     }
 
     pub fn obligation_borrow_amount_wads(
@@ -751,14 +2457,14 @@ pub mod port_accessor {
             return Err(error!(PortAdaptorError::BorrowIndexOutOfBound));
         }
         let mut amount_bytes = [0u8; 16];
-        let start_index = 140
+        let start_index = layout::obligation::ENTRIES
             + (deposit_lens as usize) * OBLIGATION_COLLATERAL_LEN
             + n as usize * OBLIGATION_LIQUIDITY_LEN
             + PUBKEY_BYTES
             + 16;
 
         amount_bytes.copy_from_slice(&bytes[start_index..(start_index + 16)]);
-        Ok(unpack_decimal(&amount_bytes))
+        Ok(parse_decimal(&amount_bytes))
     }
 
     pub fn obligation_deposit_amount(
@@ -772,11 +2478,57 @@ pub mod port_accessor {
             return Err(error!(PortAdaptorError::CollateralIndexOutOfBound));
         }
         let mut amount_bytes = [0u8; 8];
-        let start_index = 140 + n as usize * OBLIGATION_COLLATERAL_LEN + PUBKEY_BYTES;
+        let start_index =
+            layout::obligation::ENTRIES + n as usize * OBLIGATION_COLLATERAL_LEN + PUBKEY_BYTES;
 
         amount_bytes.copy_from_slice(&bytes[start_index..(start_index + 8)]);
         Ok(u64::from_le_bytes(amount_bytes))
     }
+
+    /// Market value of the `n`th deposit as of the last obligation refresh.
+    pub fn obligation_deposit_value(
+        account: &AccountInfo,
+        n: u8,
+    ) -> std::result::Result<Decimal, Error> {
+        let bytes = account.try_borrow_data()?;
+        let deposit_lens = obligation_deposits_count(account)?;
+        if n >= deposit_lens {
+            msg!("No enough deposits");
+            return Err(error!(PortAdaptorError::CollateralIndexOutOfBound));
+        }
+        let mut value_bytes = [0u8; 16];
+        let start_index =
+            layout::obligation::ENTRIES + n as usize * OBLIGATION_COLLATERAL_LEN + PUBKEY_BYTES + 8;
+
+        value_bytes.copy_from_slice(&bytes[start_index..(start_index + 16)]);
+        Ok(parse_decimal(&value_bytes))
+    }
+    pub fn obligation_deposits_range(
+        account: &AccountInfo,
+        start: u8,
+        end: u8,
+    ) -> std::result::Result<Vec<(Pubkey, u64)>, Error> {
+        let bytes = account.try_borrow_data()?;
+        let deposit_lens = obligation_deposits_count(account)?;
+        if start > end || end > deposit_lens {
+            msg!("No enough deposits");
+            return Err(error!(PortAdaptorError::CollateralIndexOutOfBound));
+        }
+        Ok((start..end)
+            .map(|n| {
+                let start_index =
+                    layout::obligation::ENTRIES + n as usize * OBLIGATION_COLLATERAL_LEN;
+                let amount_index = start_index + PUBKEY_BYTES;
+                let mut amount_bytes = [0u8; 8];
+                amount_bytes.copy_from_slice(&bytes[amount_index..(amount_index + 8)]);
+                (
+                    Pubkey::new(&bytes[start_index..amount_index]),
+                    u64::from_le_bytes(amount_bytes),
+                )
+            })
+            .collect())
+    }
+
     pub fn obligation_liquidity(
         account: &AccountInfo,
         port_exchange_rate: &CollateralExchangeRate,
@@ -797,14 +2549,116 @@ pub mod port_accessor {
         Decimal::from(deposit).try_sub(borrow).map_err(Into::into)
     }
 
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ObligationEntryKind {
+        Deposit,
+        Borrow,
+    }
+
+    /// Port removes an entry once it is fully withdrawn or repaid, shifting the
+    /// entries after it, so indices are not stable across transactions. Check
+    /// the entry still exists before reading it by index again.
+    pub fn obligation_entry_exists(
+        account: &AccountInfo,
+        reserve: &Pubkey,
+        kind: ObligationEntryKind,
+    ) -> std::result::Result<bool, Error> {
+        let bytes = account.try_borrow_data()?;
+        let deposit_lens = obligation_deposits_count(account)? as usize;
+        let (start_index, entry_len, entries) = match kind {
+            ObligationEntryKind::Deposit => (
+                layout::obligation::ENTRIES,
+                OBLIGATION_COLLATERAL_LEN,
+                deposit_lens,
+            ),
+            ObligationEntryKind::Borrow => (
+                layout::obligation::ENTRIES + deposit_lens * OBLIGATION_COLLATERAL_LEN,
+                OBLIGATION_LIQUIDITY_LEN,
+                obligation_borrows_count(account)? as usize,
+            ),
+        };
+        Ok((0..entries).any(|n| {
+            let start = start_index + n * entry_len;
+            bytes[start..(start + PUBKEY_BYTES)] == reserve.to_bytes()
+        }))
+    }
+
+    pub fn obligation_last_update_slot(account: &AccountInfo) -> std::result::Result<Slot, Error> {
+        let bytes = account.try_borrow_data()?;
+        if bytes.len() < layout::obligation::LAST_UPDATE_SLOT.end {
+            return Err(ProgramError::AccountDataTooSmall.into());
+        }
+        let mut amount_bytes = [0u8; 8];
+        amount_bytes.copy_from_slice(&bytes[layout::obligation::LAST_UPDATE_SLOT]);
+        Ok(u64::from_le_bytes(amount_bytes))
+    }
+
+    /// Deposit reserves followed by borrow reserves, in the order and with the
+    /// repeats that `refresh_obligation` expects.
+    pub fn obligation_reserves(account: &AccountInfo) -> std::result::Result<Vec<Pubkey>, Error> {
+        let bytes = account.try_borrow_data()?;
+        let deposit_lens = obligation_deposits_count(account)? as usize;
+        let borrows_lens = obligation_borrows_count(account)? as usize;
+        let borrows_start = layout::obligation::ENTRIES + deposit_lens * OBLIGATION_COLLATERAL_LEN;
+        let deposits =
+            (0..deposit_lens).map(|n| layout::obligation::ENTRIES + n * OBLIGATION_COLLATERAL_LEN);
+        let borrows = (0..borrows_lens).map(|n| borrows_start + n * OBLIGATION_LIQUIDITY_LEN);
+        Ok(deposits
+            .chain(borrows)
+            .map(|start| Pubkey::new(&bytes[start..(start + PUBKEY_BYTES)]))
+            .collect())
+    }
+
+    /// Deposit then borrow reserves with duplicates removed, in first-seen
+    /// order. This is the set of reserves to refresh before the obligation;
+    /// `refresh_port_obligation` itself expects every deposit reserve followed
+    /// by every borrow reserve, repeats included.
+    pub fn unique_obligation_reserves(
+        account: &AccountInfo,
+    ) -> std::result::Result<Vec<Pubkey>, Error> {
+        let mut reserves: Vec<Pubkey> = Vec::new();
+        for reserve in obligation_reserves(account)? {
+            if !reserves.contains(&reserve) {
+                reserves.push(reserve);
+            }
+        }
+        Ok(reserves)
+    }
+
     pub fn is_obligation_stale(account: &AccountInfo) -> std::result::Result<bool, Error> {
         let bytes = account.try_borrow_data()?;
-        Ok(bytes[9] == 1)
+        Ok(bytes[layout::obligation::LAST_UPDATE_STALE] == 1)
+    }
+
+    pub fn reserve_last_update_slot(account: &AccountInfo) -> std::result::Result<Slot, Error> {
+        let bytes = account.try_borrow_data()?;
+        if bytes.len() < layout::reserve::LAST_UPDATE_SLOT.end {
+            return Err(ProgramError::AccountDataTooSmall.into());
+        }
+        let mut amount_bytes = [0u8; 8];
+        amount_bytes.copy_from_slice(&bytes[layout::reserve::LAST_UPDATE_SLOT]);
+        Ok(u64::from_le_bytes(amount_bytes))
+    }
+
+    /// Port does not store the oracle's publish slot. The market price is only
+    /// written by `refresh_reserve`, so the reserve's last update slot is the
+    /// slot the price was last read from the oracle, not the slot the oracle
+    /// itself last updated.
+    pub fn reserve_market_price_slot(account: &AccountInfo) -> std::result::Result<Slot, Error> {
+        reserve_last_update_slot(account)
+    }
+
+    pub fn is_price_stale(
+        account: &AccountInfo,
+        current_slot: Slot,
+        max_age: u64,
+    ) -> std::result::Result<bool, Error> {
+        Ok(current_slot.saturating_sub(reserve_market_price_slot(account)?) > max_age)
     }
 
     pub fn is_reserve_stale(account: &AccountInfo) -> std::result::Result<bool, Error> {
         let bytes = account.try_borrow_data()?;
-        Ok(bytes[9] == 1)
+        Ok(bytes[layout::reserve::LAST_UPDATE_STALE] == 1)
     }
 }
 #[derive(Clone)]
@@ -812,6 +2666,20 @@ pub struct PortStakeAccount(StakeAccount);
 
 impl PortStakeAccount {
     pub const LEN: usize = StakeAccount::LEN;
+
+    pub fn last_reward_index(&self) -> StakingDecimal {
+        self.start_rate
+    }
+
+    pub fn sub_reward_index(&self) -> Option<StakingDecimal> {
+        self.sub_start_rate
+    }
+
+    /// The staking program rejects claims before the pool's
+    /// `earliest_reward_claim_time`, which is a slot.
+    pub fn can_claim(&self, pool: &PortStakingPool, current_slot: Slot) -> bool {
+        current_slot >= pool.earliest_reward_claim_time
+    }
 }
 
 impl anchor_lang::AccountDeserialize for PortStakeAccount {
@@ -847,9 +2715,298 @@ impl Deref for PortStakeAccount {
     }
 }
 
+impl AsRef<StakeAccount> for PortStakeAccount {
+    fn as_ref(&self) -> &StakeAccount {
+        &self.0
+    }
+}
+
+impl From<PortStakeAccount> for StakeAccount {
+    fn from(wrapper: PortStakeAccount) -> Self {
+        wrapper.0
+    }
+}
+
+impl From<StakeAccount> for PortStakeAccount {
+    fn from(stake_account: StakeAccount) -> Self {
+        PortStakeAccount(stake_account)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReserveSnapshot {
+    pub utilization_rate: solana_maths::Rate,
+    pub borrow_apy: solana_maths::Rate,
+    pub supply_apy: solana_maths::Rate,
+    pub total_liquidity: Decimal,
+}
+
+/// Change from a previous snapshot, with each field the signed difference of
+/// the WAD-scaled values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReserveDelta {
+    pub utilization_rate: i128,
+    pub borrow_apy: i128,
+    pub supply_apy: i128,
+    pub total_liquidity: i128,
+}
+
+impl ReserveSnapshot {
+    pub fn diff(&self, previous: &ReserveSnapshot) -> std::result::Result<ReserveDelta, Error> {
+        Ok(ReserveDelta {
+            utilization_rate: scaled_delta(
+                self.utilization_rate.to_scaled_val(),
+                previous.utilization_rate.to_scaled_val(),
+            )?,
+            borrow_apy: scaled_delta(
+                self.borrow_apy.to_scaled_val(),
+                previous.borrow_apy.to_scaled_val(),
+            )?,
+            supply_apy: scaled_delta(
+                self.supply_apy.to_scaled_val(),
+                previous.supply_apy.to_scaled_val(),
+            )?,
+            total_liquidity: scaled_delta(
+                self.total_liquidity.to_scaled_val()?,
+                previous.total_liquidity.to_scaled_val()?,
+            )?,
+        })
+    }
+}
+
+fn scaled_delta(current: u128, previous: u128) -> std::result::Result<i128, Error> {
+    i128::try_from(current)
+        .ok()
+        .zip(i128::try_from(previous).ok())
+        .and_then(|(current, previous)| current.checked_sub(previous))
+        .ok_or_else(|| ProgramError::from(LendingError::MathOverflow).into())
+}
+
+fn to_solana_rate(rate: Rate) -> std::result::Result<solana_maths::Rate, Error> {
+    u64::try_from(rate.to_scaled_val())
+        .map(solana_maths::Rate::from_scaled_val)
+        .map_err(|_| ProgramError::from(LendingError::MathOverflow).into())
+}
+
 #[derive(Clone)]
 pub struct PortReserve(Reserve);
 
+impl PortReserve {
+    pub const LEN: usize = Reserve::LEN;
+
+    pub fn oracle(&self) -> Option<Pubkey> {
+        match self.liquidity.oracle_pubkey {
+            COption::Some(oracle) => Some(oracle),
+            COption::None => None,
+        }
+    }
+
+    pub fn staking_pool(&self) -> Option<Pubkey> {
+        match self.config.deposit_staking_pool {
+            COption::Some(staking_pool) => Some(staking_pool),
+            COption::None => None,
+        }
+    }
+
+    pub fn interest_rate_curve(&self) -> rates::InterestRateCurve {
+        rates::InterestRateCurve {
+            optimal_utilization_rate: self.config.optimal_utilization_rate,
+            min_borrow_rate: self.config.min_borrow_rate,
+            optimal_borrow_rate: self.config.optimal_borrow_rate,
+            max_borrow_rate: self.config.max_borrow_rate,
+        }
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.liquidity.mint_pubkey == spl_token::native_mint::id()
+    }
+
+    pub fn needs_refresh(&self, current_slot: Slot) -> bool {
+        self.last_update.stale || self.last_update.slot != current_slot
+    }
+
+    /// Borrow and supply APYs compound the current annual rates once per slot.
+    pub fn snapshot(&self) -> std::result::Result<ReserveSnapshot, Error> {
+        let utilization_rate = self.liquidity.utilization_rate()?;
+        let borrow_rate = self.current_borrow_rate()?;
+        let supply_rate = borrow_rate.try_mul(utilization_rate)?;
+        Ok(ReserveSnapshot {
+            utilization_rate: to_solana_rate(utilization_rate)?,
+            borrow_apy: rates::apr_to_apy(to_solana_rate(borrow_rate)?, rates::SLOTS_PER_YEAR)?,
+            supply_apy: rates::apr_to_apy(to_solana_rate(supply_rate)?, rates::SLOTS_PER_YEAR)?,
+            total_liquidity: self.liquidity.total_supply()?,
+        })
+    }
+
+    pub fn exchange_rate(&self) -> std::result::Result<CollateralExchangeRate, Error> {
+        let mint_total_supply = self.collateral.mint_total_supply;
+        let total_liquidity = self.liquidity.total_supply()?;
+        let rate = if mint_total_supply == 0 || total_liquidity == Decimal::zero() {
+            Rate::from_scaled_val(INITIAL_COLLATERAL_RATE)
+        } else {
+            let mint_total_supply = Decimal::from(mint_total_supply);
+            Rate::try_from(mint_total_supply.try_div(total_liquidity)?)?
+        };
+        Ok(CollateralExchangeRate(rate))
+    }
+
+    /// Returns `(host_fee, total_fee)` for borrowing `amount`. The host fee is
+    /// the share of the total fee paid to the host fee receiver.
+    pub fn borrow_fee_amount(&self, amount: u64) -> std::result::Result<(u64, u64), Error> {
+        let (total_fee, host_fee) = self
+            .config
+            .fees
+            .calculate_borrow_fees(Decimal::from(amount), FeeCalculation::Exclusive)?;
+        Ok((host_fee, total_fee))
+    }
+
+    /// Port has no borrow cap on a reserve, so the only limit is available
+    /// liquidity, which has to cover the amount plus the borrow fee.
+    pub fn can_borrow(&self, amount: u64) -> std::result::Result<bool, Error> {
+        let (borrow_fee, _) = self
+            .config
+            .fees
+            .calculate_borrow_fees(Decimal::from(amount), FeeCalculation::Exclusive)?;
+        Ok(amount
+            .checked_add(borrow_fee)
+            .map_or(false, |total| total <= self.liquidity.available_amount))
+    }
+
+    /// The largest amount `can_borrow` accepts. With no borrow cap this is the
+    /// available liquidity less the borrow fee charged on top of the amount.
+    pub fn max_borrowable(&self) -> std::result::Result<u64, Error> {
+        let available_amount = self.liquidity.available_amount;
+        if available_amount == 0 {
+            return Ok(0);
+        }
+        let (borrow_fee, _) = self
+            .config
+            .fees
+            .calculate_borrow_fees(Decimal::from(available_amount), FeeCalculation::Inclusive)?;
+        Ok(available_amount.saturating_sub(borrow_fee))
+    }
+
+    /// Builds `Deposit` for this reserve, picking the reserve-side accounts,
+    /// lending market authority, clock and token program out of `accounts` by
+    /// the keys the reserve records. `reserve` must hold this reserve: its
+    /// lending market, liquidity supply and collateral mint are checked.
+    pub fn deposit_accounts<'info>(
+        &self,
+        reserve: AccountInfo<'info>,
+        source_liquidity: AccountInfo<'info>,
+        destination_collateral: AccountInfo<'info>,
+        transfer_authority: AccountInfo<'info>,
+        accounts: &[AccountInfo<'info>],
+        program_id: &Pubkey,
+    ) -> std::result::Result<Deposit<'info>, Error> {
+        let passed = PortReserve::try_deserialize(&mut &reserve.try_borrow_data()?[..])?;
+        if passed.lending_market != self.lending_market
+            || passed.liquidity.supply_pubkey != self.liquidity.supply_pubkey
+            || passed.collateral.mint_pubkey != self.collateral.mint_pubkey
+        {
+            msg!(
+                "Reserve {} does not match the reserve the accounts are built from",
+                reserve.key()
+            );
+            return Err(error!(PortAdaptorError::ReserveAccountMismatch));
+        }
+        let find = |key: Pubkey| {
+            accounts
+                .iter()
+                .find(|info| info.key() == key)
+                .cloned()
+                .ok_or_else(|| {
+                    msg!("Missing account {}", key);
+                    error!(anchor_lang::error::ErrorCode::AccountNotEnoughKeys)
+                })
+        };
+        Ok(Deposit {
+            source_liquidity,
+            destination_collateral,
+            reserve,
+            reserve_liquidity_supply: find(self.liquidity.supply_pubkey)?,
+            reserve_collateral_mint: find(self.collateral.mint_pubkey)?,
+            lending_market: find(self.lending_market)?,
+            lending_market_authority: find(
+                lending_market_authority(&self.lending_market, program_id).0,
+            )?,
+            transfer_authority,
+            clock: find(anchor_lang::solana_program::sysvar::clock::ID)?,
+            token_program: find(spl_token::id())?,
+        })
+    }
+
+    pub fn market_value(&self, liquidity_amount: Decimal) -> std::result::Result<Decimal, Error> {
+        let decimals = 10u64
+            .checked_pow(self.liquidity.mint_decimals as u32)
+            .ok_or(ProgramError::from(LendingError::MathOverflow))?;
+        liquidity_amount
+            .try_mul(self.liquidity.market_price)?
+            .try_div(decimals)
+            .map_err(Into::into)
+    }
+
+    pub fn liquidity_amount_for_value(&self, value: Decimal) -> std::result::Result<u64, Error> {
+        let decimals = 10u64
+            .checked_pow(self.liquidity.mint_decimals as u32)
+            .ok_or(ProgramError::from(LendingError::MathOverflow))?;
+        value
+            .try_mul(decimals)?
+            .try_div(self.liquidity.market_price)?
+            .try_floor_u64()
+            .map_err(Into::into)
+    }
+
+    /// Collateral to deposit in this reserve so that `borrow_value` is allowed
+    /// at a loan to value ratio of `ltv` percent, rounded up. No collateral
+    /// allows a borrow at an `ltv` of zero, so that is `InvalidLtv`.
+    pub fn collateral_for_borrow_value(
+        &self,
+        borrow_value: Decimal,
+        ltv: u8,
+    ) -> std::result::Result<u64, Error> {
+        if ltv == 0 {
+            msg!("Cannot borrow against collateral at a zero loan to value ratio");
+            return Err(error!(PortAdaptorError::InvalidLtv));
+        }
+        let decimals = 10u64
+            .checked_pow(self.liquidity.mint_decimals as u32)
+            .ok_or(ProgramError::from(LendingError::MathOverflow))?;
+        let liquidity_amount = borrow_value
+            .try_div(Rate::from_percent(ltv))?
+            .try_mul(decimals)?
+            .try_div(self.liquidity.market_price)?;
+        self.collateral_exchange_rate()?
+            .decimal_liquidity_to_collateral(liquidity_amount)?
+            .try_ceil_u64()
+            .map_err(Into::into)
+    }
+
+    pub fn collateral_market_value(
+        &self,
+        collateral_amount: u64,
+    ) -> std::result::Result<Decimal, Error> {
+        let liquidity_amount = self
+            .collateral_exchange_rate()?
+            .decimal_collateral_to_liquidity(Decimal::from(collateral_amount))?;
+        self.market_value(liquidity_amount)
+    }
+
+    /// Port charges no fee on deposits or redemptions, so the net amounts are
+    /// the plain exchange-rate conversions.
+    pub fn net_redeem_liquidity(&self, collateral_amount: u64) -> std::result::Result<u64, Error> {
+        self.collateral_exchange_rate()?
+            .collateral_to_liquidity(collateral_amount)
+            .map_err(Into::into)
+    }
+
+    pub fn net_deposit_collateral(&self, liquidity_amount: u64) -> std::result::Result<u64, Error> {
+        self.collateral_exchange_rate()?
+            .liquidity_to_collateral(liquidity_amount)
+            .map_err(Into::into)
+    }
+}
+
 impl anchor_lang::AccountDeserialize for PortReserve {
     fn try_deserialize(buf: &mut &[u8]) -> std::result::Result<Self, Error> {
         PortReserve::try_deserialize_unchecked(buf)
@@ -881,11 +3038,40 @@ impl Deref for PortReserve {
     }
 }
 
+impl AsRef<Reserve> for PortReserve {
+    fn as_ref(&self) -> &Reserve {
+        &self.0
+    }
+}
+
+impl From<PortReserve> for Reserve {
+    fn from(wrapper: PortReserve) -> Self {
+        wrapper.0
+    }
+}
+
 #[derive(Clone)]
 pub struct PortObligation(Obligation);
 
 impl PortObligation {
     pub const LEN: usize = Obligation::LEN;
+
+    /// `PortObligation` is a snapshot of the account data at deserialization.
+    /// Reload with this after any CPI that changes the obligation; the
+    /// `port_accessor` functions always read the live data.
+    pub fn from_fresh(info: &AccountInfo) -> std::result::Result<Self, Error> {
+        let data = info.try_borrow_data()?;
+        PortObligation::try_deserialize(&mut &data[..])
+    }
+
+    pub fn deposit_count(&self) -> usize {
+        self.deposits.len()
+    }
+
+    pub fn borrow_count(&self) -> usize {
+        self.borrows.len()
+    }
+
     pub fn calculate_liquidity(
         &self,
         reserve_pubkey: &Pubkey,
@@ -919,6 +3105,171 @@ impl PortObligation {
             .checked_sub(borrow.try_ceil_u64()?)
             .ok_or(error!(PortAdaptorError::Insolvency))
     }
+
+    pub fn net_value(&self) -> std::result::Result<Decimal, Error> {
+        if self.borrowed_value > self.deposited_value {
+            return Err(error!(PortAdaptorError::Insolvency));
+        }
+        self.deposited_value
+            .try_sub(self.borrowed_value)
+            .map_err(Into::into)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deposits.is_empty() && self.borrows.is_empty()
+    }
+
+    pub fn total_borrowed_value(&self) -> std::result::Result<Decimal, Error> {
+        self.borrows
+            .iter()
+            .try_fold(Decimal::zero(), |total, b| total.try_add(b.market_value))
+            .map_err(Into::into)
+    }
+
+    pub fn total_deposited_value(&self) -> std::result::Result<Decimal, Error> {
+        self.deposits
+            .iter()
+            .try_fold(Decimal::zero(), |total, d| total.try_add(d.market_value))
+            .map_err(Into::into)
+    }
+
+    /// Scales the stored borrow by the growth of the reserve's cumulative borrow
+    /// rate since the obligation was last refreshed.
+    pub fn current_borrow_wads(
+        &self,
+        reserve_pubkey: &Pubkey,
+        reserve: &PortReserve,
+    ) -> std::result::Result<Decimal, Error> {
+        let borrow = match self
+            .borrows
+            .iter()
+            .find(|b| b.borrow_reserve == *reserve_pubkey)
+        {
+            Some(borrow) => borrow,
+            None => return Ok(Decimal::zero()),
+        };
+        borrow
+            .borrowed_amount_wads
+            .try_mul(reserve.liquidity.cumulative_borrow_rate_wads)?
+            .try_div(borrow.cumulative_borrow_rate_wads)
+            .map_err(Into::into)
+    }
+
+    /// The part of `allowed_borrow_value` contributed by the deposit in
+    /// `reserve_pubkey`, using the market value from the last refresh.
+    pub fn borrowing_power_from(
+        &self,
+        reserve_pubkey: &Pubkey,
+        reserve: &PortReserve,
+    ) -> std::result::Result<Decimal, Error> {
+        let deposit = match self
+            .deposits
+            .iter()
+            .find(|d| d.deposit_reserve == *reserve_pubkey)
+        {
+            Some(deposit) => deposit,
+            None => return Ok(Decimal::zero()),
+        };
+        deposit
+            .market_value
+            .try_mul(Rate::from_percent(reserve.config.loan_to_value_ratio))
+            .map_err(Into::into)
+    }
+
+    /// Deposits whose reserve is missing from `market` contribute nothing.
+    pub fn borrowing_power(&self, market: &PortMarket) -> std::result::Result<Decimal, Error> {
+        market
+            .reserves
+            .iter()
+            .try_fold(Decimal::zero(), |total, (key, reserve)| {
+                total
+                    .try_add(self.borrowing_power_from(key, reserve)?)
+                    .map_err(Into::into)
+            })
+    }
+
+    pub fn largest_deposit(&self) -> Option<(Pubkey, Decimal)> {
+        self.deposits
+            .iter()
+            .max_by_key(|d| d.market_value)
+            .map(|d| (d.deposit_reserve, d.market_value))
+    }
+
+    pub fn largest_borrow(&self) -> Option<(Pubkey, Decimal)> {
+        self.borrows
+            .iter()
+            .max_by_key(|b| b.market_value)
+            .map(|b| (b.borrow_reserve, b.market_value))
+    }
+
+    /// Collateral tokens of `reserve_pubkey` that can be withdrawn while keeping
+    /// `borrowed_value <= allowed_borrow_value`, given that removing a value `v`
+    /// of collateral lowers the allowed borrow value by `v` times the reserve's
+    /// loan to value ratio.
+    pub fn max_withdraw_collateral(
+        &self,
+        reserve_pubkey: &Pubkey,
+        reserve: &PortReserve,
+    ) -> std::result::Result<u64, Error> {
+        let deposit = match self
+            .deposits
+            .iter()
+            .find(|d| d.deposit_reserve == *reserve_pubkey)
+        {
+            Some(deposit) => deposit,
+            None => return Ok(0),
+        };
+        let ltv = reserve.config.loan_to_value_ratio;
+        if self.borrowed_value == Decimal::zero()
+            || ltv == 0
+            || deposit.market_value == Decimal::zero()
+        {
+            return Ok(deposit.deposited_amount);
+        }
+        let withdraw_value = self.max_borrow_value().try_div(Rate::from_percent(ltv))?;
+        if withdraw_value >= deposit.market_value {
+            return Ok(deposit.deposited_amount);
+        }
+        Decimal::from(deposit.deposited_amount)
+            .try_mul(withdraw_value)?
+            .try_div(deposit.market_value)?
+            .try_floor_u64()
+            .map_err(Into::into)
+    }
+
+    /// Picks the largest borrow to repay and the largest deposit to seize, as
+    /// `(repay_reserve, withdraw_reserve)`. Values are from the last refresh.
+    pub fn liquidation_accounts(&self) -> std::result::Result<(Pubkey, Pubkey), Error> {
+        let (repay_reserve, _) = self.largest_borrow().ok_or_else(|| {
+            msg!("Obligation has no borrows to liquidate");
+            error!(PortAdaptorError::BorrowIndexOutOfBound)
+        })?;
+        let (withdraw_reserve, _) = self.largest_deposit().ok_or_else(|| {
+            msg!("Obligation has no deposits to seize");
+            error!(PortAdaptorError::CollateralIndexOutOfBound)
+        })?;
+        Ok((repay_reserve, withdraw_reserve))
+    }
+
+    /// Obligations don't record staking; a deposit is staked when its reserve
+    /// has a staking pool, which `market` must include to be detected.
+    pub fn has_staked_collateral(&self, market: &PortMarket) -> bool {
+        self.deposits.iter().any(|d| {
+            market
+                .reserve(&d.deposit_reserve)
+                .map_or(false, |reserve| reserve.staking_pool().is_some())
+        })
+    }
+
+    pub fn max_borrow_value(&self) -> Decimal {
+        self.allowed_borrow_value
+            .try_sub(self.borrowed_value)
+            .unwrap_or_else(|_| Decimal::zero())
+    }
+
+    pub fn max_borrow_amount(&self, reserve: &PortReserve) -> std::result::Result<u64, Error> {
+        reserve.liquidity_amount_for_value(self.max_borrow_value())
+    }
 }
 
 impl anchor_lang::AccountDeserialize for PortObligation {
@@ -954,11 +3305,91 @@ impl Deref for PortObligation {
     }
 }
 
+impl AsRef<Obligation> for PortObligation {
+    fn as_ref(&self) -> &Obligation {
+        &self.0
+    }
+}
+
+impl From<PortObligation> for Obligation {
+    fn from(wrapper: PortObligation) -> Self {
+        wrapper.0
+    }
+}
+
+/// The pool records only the reward token account for each reward; the mint and
+/// remaining supply are read from that account, e.g. with
+/// `token_account_balance`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RewardInfo {
+    pub reward_token_pool: Pubkey,
+    pub rate_per_slot: StakingDecimal,
+}
+
 #[derive(Clone)]
 pub struct PortStakingPool(StakingPool);
 
 impl PortStakingPool {
     pub const LEN: usize = StakingPool::LEN;
+
+    pub fn ends_at_slot(&self) -> Slot {
+        self.end_time
+    }
+
+    pub fn starts_at_slot(&self) -> Slot {
+        self.end_time.saturating_sub(self.duration)
+    }
+
+    pub fn is_active(&self, current_slot: Slot) -> bool {
+        self.starts_at_slot() <= current_slot && current_slot < self.end_time
+    }
+
+    pub fn remaining_slots(&self, current_slot: Slot) -> u64 {
+        self.end_time.saturating_sub(current_slot)
+    }
+
+    pub fn elapsed_slots(&self, current_slot: Slot) -> u64 {
+        current_slot
+            .min(self.end_time)
+            .saturating_sub(self.starts_at_slot())
+    }
+
+    pub fn rewards(&self) -> Vec<RewardInfo> {
+        let mut rewards = vec![RewardInfo {
+            reward_token_pool: self.reward_token_pool,
+            rate_per_slot: self.rate_per_slot,
+        }];
+        if let (Some(reward_token_pool), Some(rate_per_slot)) =
+            (self.sub_reward_token_pool, self.sub_rate_per_slot)
+        {
+            rewards.push(RewardInfo {
+                reward_token_pool,
+                rate_per_slot,
+            });
+        }
+        rewards
+    }
+
+    /// Primary reward emitted per slot across the whole pool, in raw token units.
+    pub fn rewards_per_slot(&self) -> StakingDecimal {
+        self.rate_per_slot
+    }
+
+    /// `rewards_per_slot` in whole tokens for display. `f64` keeps about 15
+    /// significant digits, so don't feed the result back into on-chain math.
+    pub fn rewards_per_slot_ui(&self, decimals: u8) -> std::result::Result<f64, Error> {
+        let scaled = self.rate_per_slot.to_scaled_val()?;
+        let wad = StakingDecimal::one().to_scaled_val()?;
+        Ok(scaled as f64 / wad as f64 / 10f64.powi(decimals as i32))
+    }
+
+    pub fn share_of(&self, stake_account: &PortStakeAccount) -> std::result::Result<Rate, Error> {
+        if self.pool_size == 0 {
+            return Ok(Rate::zero());
+        }
+        let share = Decimal::from(stake_account.deposited_amount).try_div(self.pool_size)?;
+        Rate::try_from(share).map_err(Into::into)
+    }
 }
 
 impl anchor_lang::AccountDeserialize for PortStakingPool {
@@ -994,6 +3425,24 @@ impl Deref for PortStakingPool {
     }
 }
 
+impl AsRef<StakingPool> for PortStakingPool {
+    fn as_ref(&self) -> &StakingPool {
+        &self.0
+    }
+}
+
+impl From<PortStakingPool> for StakingPool {
+    fn from(wrapper: PortStakingPool) -> Self {
+        wrapper.0
+    }
+}
+
+impl From<StakingPool> for PortStakingPool {
+    fn from(staking_pool: StakingPool) -> Self {
+        PortStakingPool(staking_pool)
+    }
+}
+
 #[derive(Clone)]
 pub struct PortLendingMarket(LendingMarket);
 
@@ -1033,3 +3482,110 @@ impl Deref for PortLendingMarket {
         &self.0
     }
 }
+
+impl AsRef<LendingMarket> for PortLendingMarket {
+    fn as_ref(&self) -> &LendingMarket {
+        &self.0
+    }
+}
+
+impl From<PortLendingMarket> for LendingMarket {
+    fn from(wrapper: PortLendingMarket) -> Self {
+        wrapper.0
+    }
+}
+
+#[derive(Clone)]
+pub struct PortMarket {
+    pub key: Pubkey,
+    pub lending_market: PortLendingMarket,
+    pub reserves: Vec<(Pubkey, PortReserve)>,
+}
+
+impl PortMarket {
+    pub fn load(
+        market: &AccountInfo,
+        reserves: &[AccountInfo],
+        program_id: &Pubkey,
+    ) -> std::result::Result<PortMarket, Error> {
+        assert_owned_by(market, program_id)?;
+        let lending_market =
+            PortLendingMarket::try_deserialize(&mut &market.try_borrow_data()?[..])?;
+        let reserves = reserves
+            .iter()
+            .map(|info| {
+                assert_owned_by(info, program_id)?;
+                let reserve = PortReserve::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+                if reserve.lending_market != market.key() {
+                    msg!(
+                        "Reserve {} does not belong to lending market {}",
+                        info.key(),
+                        market.key()
+                    );
+                    return Err(error!(PortAdaptorError::ReserveMarketMismatch));
+                }
+                Ok((info.key(), reserve))
+            })
+            .collect::<std::result::Result<Vec<_>, Error>>()?;
+
+        Ok(PortMarket {
+            key: market.key(),
+            lending_market,
+            reserves,
+        })
+    }
+
+    pub fn reserve(&self, key: &Pubkey) -> Option<&PortReserve> {
+        self.reserves
+            .iter()
+            .find_map(|(k, reserve)| if k == key { Some(reserve) } else { None })
+    }
+
+    /// Staking pools don't record their reserve; the link is only stored in
+    /// the reserve config, so the reverse lookup goes through the market.
+    pub fn reserve_for_staking_pool(&self, staking_pool: &Pubkey) -> Option<Pubkey> {
+        self.reserves.iter().find_map(|(key, reserve)| {
+            if reserve.staking_pool().as_ref() == Some(staking_pool) {
+                Some(*key)
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn total_deposits_value(&self) -> std::result::Result<Decimal, Error> {
+        self.reserves
+            .iter()
+            .try_fold(Decimal::zero(), |total, (_, reserve)| {
+                let value = reserve.market_value(reserve.liquidity.total_supply()?)?;
+                total.try_add(value).map_err(Into::into)
+            })
+    }
+
+    /// Reserves without an oracle have no meaningful price; they are left out
+    /// when `skip_unpriced` is set and are an error otherwise.
+    pub fn total_value_locked(&self, skip_unpriced: bool) -> std::result::Result<Decimal, Error> {
+        self.reserves
+            .iter()
+            .try_fold(Decimal::zero(), |total, (key, reserve)| {
+                if reserve.oracle().is_none() {
+                    if skip_unpriced {
+                        return Ok(total);
+                    }
+                    msg!("Reserve {} has no oracle", key);
+                    return Err(error!(PortAdaptorError::MissingOracle));
+                }
+                let value = reserve.market_value(reserve.liquidity.total_supply()?)?;
+                total.try_add(value).map_err(Into::into)
+            })
+    }
+
+    pub fn total_borrows_value(&self) -> std::result::Result<Decimal, Error> {
+        self.reserves
+            .iter()
+            .try_fold(Decimal::zero(), |total, (_, reserve)| {
+                let value = reserve.market_value(reserve.liquidity.borrowed_amount_wads)?;
+                total.try_add(value).map_err(Into::into)
+            })
+    }
+}
@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+use solana_maths::{Rate, TryAdd, TryDiv, TryMul, TrySub};
+
+pub use port_variable_rate_lending_instructions::state::SLOTS_PER_YEAR;
+
+pub fn slot_rate_to_apr(rate: Rate, slots_per_year: u64) -> std::result::Result<Rate, Error> {
+    rate.try_mul(slots_per_year).map_err(Into::into)
+}
+
+pub fn apr_to_apy(apr: Rate, compounding_periods: u64) -> std::result::Result<Rate, Error> {
+    Rate::one()
+        .try_add(apr.try_div(compounding_periods)?)?
+        .try_pow(compounding_periods)?
+        .try_sub(Rate::one())
+        .map_err(Into::into)
+}
+
+/// APY of depositing `leverage` times the equity while borrowing the rest.
+/// Rates are unsigned, so a position whose borrow cost exceeds its yield
+/// reports zero.
+pub fn net_leveraged_apy(
+    supply_apy: Rate,
+    borrow_apy: Rate,
+    leverage: Rate,
+) -> std::result::Result<Rate, Error> {
+    let borrowed = leverage
+        .try_sub(Rate::one())
+        .unwrap_or_else(|_| Rate::zero());
+    let earned = supply_apy.try_mul(leverage)?;
+    let cost = borrow_apy.try_mul(borrowed)?;
+    Ok(earned.try_sub(cost).unwrap_or_else(|_| Rate::zero()))
+}
+
+/// The piecewise linear borrow rate curve of a reserve, with every field a
+/// percentage as stored in the reserve config.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InterestRateCurve {
+    pub optimal_utilization_rate: u8,
+    pub min_borrow_rate: u8,
+    pub optimal_borrow_rate: u8,
+    pub max_borrow_rate: u8,
+}
+
+impl InterestRateCurve {
+    pub fn evaluate(&self, utilization: Rate) -> std::result::Result<Rate, Error> {
+        let optimal_utilization = Rate::from_percent(self.optimal_utilization_rate);
+        if utilization < optimal_utilization || self.optimal_utilization_rate == 100 {
+            let normalized_rate = utilization.try_div(optimal_utilization)?;
+            let rate_range = Rate::from_percent(
+                self.optimal_borrow_rate
+                    .saturating_sub(self.min_borrow_rate),
+            );
+            normalized_rate
+                .try_mul(rate_range)?
+                .try_add(Rate::from_percent(self.min_borrow_rate))
+                .map_err(Into::into)
+        } else {
+            let normalized_rate =
+                utilization
+                    .try_sub(optimal_utilization)?
+                    .try_div(Rate::from_percent(
+                        100u8.saturating_sub(self.optimal_utilization_rate),
+                    ))?;
+            let rate_range = Rate::from_percent(
+                self.max_borrow_rate
+                    .saturating_sub(self.optimal_borrow_rate),
+            );
+            normalized_rate
+                .try_mul(rate_range)?
+                .try_add(Rate::from_percent(self.optimal_borrow_rate))
+                .map_err(Into::into)
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Account buffers for exercising the staleness checks without a refresh CPI,
+//! and a recorder for the CPIs the wrappers make off-chain.
+//! Only built with the `test-utils` feature.
+
+use std::cell::RefCell;
+
+use anchor_lang::solana_program::account_info::AccountInfo;
+use anchor_lang::solana_program::clock::Slot;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::program_stubs::{self, SyscallStubs};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use port_variable_rate_lending_instructions::state::{LastUpdate, Obligation, PROGRAM_VERSION};
+
+pub fn obligation_data(stale: bool, last_update_slot: Slot) -> Vec<u8> {
+    let obligation = Obligation {
+        version: PROGRAM_VERSION,
+        last_update: LastUpdate {
+            slot: last_update_slot,
+            stale,
+        },
+        ..Obligation::default()
+    };
+    let mut data = vec![0u8; Obligation::LEN];
+    Obligation::pack(obligation, &mut data).unwrap();
+    data
+}
+
+/// A CPI captured by `record_cpis`: the instruction, the keys of the account
+/// infos handed to `invoke`, and the signer seeds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedCpi {
+    pub instruction: Instruction,
+    pub account_infos: Vec<Pubkey>,
+    pub signer_seeds: Vec<Vec<Vec<u8>>>,
+}
+
+thread_local! {
+    static RECORDED: RefCell<Vec<RecordedCpi>> = RefCell::new(Vec::new());
+}
+
+struct RecordingStubs;
+
+impl SyscallStubs for RecordingStubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let cpi = RecordedCpi {
+            instruction: instruction.clone(),
+            account_infos: account_infos.iter().map(|info| *info.key).collect(),
+            signer_seeds: signers_seeds
+                .iter()
+                .map(|seeds| seeds.iter().map(|seed| seed.to_vec()).collect())
+                .collect(),
+        };
+        RECORDED.with(|recorded| recorded.borrow_mut().push(cpi));
+        Ok(())
+    }
+}
+
+/// Runs `f` with every CPI recorded and answered with `Ok`, returning its
+/// result and the CPIs it made in order. Recording is per thread, so tests
+/// can use it in parallel.
+pub fn record_cpis<T>(f: impl FnOnce() -> T) -> (T, Vec<RecordedCpi>) {
+    program_stubs::set_syscall_stubs(Box::new(RecordingStubs));
+    RECORDED.with(|recorded| recorded.borrow_mut().clear());
+    let result = f();
+    (result, RECORDED.with(|recorded| recorded.take()))
+}
@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+use port_anchor_adaptor::{
+    borrow_account_metas, deposit_account_metas, lending_market_authority, port_lending_id,
+    BorrowKeys, DepositKeys,
+};
+
+fn pubkeys(metas: Vec<AccountMeta>) -> Vec<Pubkey> {
+    metas.into_iter().map(|meta| meta.pubkey).collect()
+}
+
+#[test]
+fn deposit_metas_follow_the_invoke_order() {
+    let keys = DepositKeys {
+        source_liquidity: Pubkey::new_unique(),
+        destination_collateral: Pubkey::new_unique(),
+        reserve: Pubkey::new_unique(),
+        reserve_liquidity_supply: Pubkey::new_unique(),
+        reserve_collateral_mint: Pubkey::new_unique(),
+        lending_market: Pubkey::new_unique(),
+        transfer_authority: Pubkey::new_unique(),
+    };
+    // the accounts `deposit_reserve` hands to invoke, minus the program
+    let invoked = vec![
+        keys.source_liquidity,
+        keys.destination_collateral,
+        keys.reserve,
+        keys.reserve_liquidity_supply,
+        keys.reserve_collateral_mint,
+        keys.lending_market,
+        lending_market_authority(&keys.lending_market, &port_lending_id()).0,
+        keys.transfer_authority,
+        sysvar::clock::id(),
+        spl_token::id(),
+    ];
+    assert_eq!(
+        pubkeys(deposit_account_metas(port_lending_id(), &keys)),
+        invoked
+    );
+}
+
+#[test]
+fn borrow_metas_follow_the_invoke_order() {
+    let keys = BorrowKeys {
+        source_liquidity: Pubkey::new_unique(),
+        destination_liquidity: Pubkey::new_unique(),
+        reserve: Pubkey::new_unique(),
+        reserve_fee_receiver: Pubkey::new_unique(),
+        obligation: Pubkey::new_unique(),
+        lending_market: Pubkey::new_unique(),
+        obligation_owner: Pubkey::new_unique(),
+    };
+    // the accounts `borrow` hands to invoke, minus the program
+    let invoked = vec![
+        keys.source_liquidity,
+        keys.destination_liquidity,
+        keys.reserve,
+        keys.reserve_fee_receiver,
+        keys.obligation,
+        keys.lending_market,
+        lending_market_authority(&keys.lending_market, &port_lending_id()).0,
+        keys.obligation_owner,
+        sysvar::clock::id(),
+        spl_token::id(),
+    ];
+    assert_eq!(
+        pubkeys(borrow_account_metas(port_lending_id(), &keys)),
+        invoked
+    );
+}
@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use port_anchor_adaptor::rates::SLOTS_PER_YEAR;
+use port_anchor_adaptor::{port_accessor, port_lending_id};
+use port_variable_rate_lending_instructions::state::{Reserve, PROGRAM_VERSION};
+use solana_maths::{Decimal, Rate, TryAdd, TryDiv, TryMul, TrySub};
+
+/// Half utilized, below the 80% kink of a 0% -> 8% curve, so the borrow rate
+/// is 0.5 / 0.8 * 8% = 5%.
+fn reserve_data() -> Vec<u8> {
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.liquidity.available_amount = 500;
+    reserve.liquidity.borrowed_amount_wads = 500u64.into();
+    reserve.config.optimal_utilization_rate = 80;
+    reserve.config.min_borrow_rate = 0;
+    reserve.config.optimal_borrow_rate = 8;
+    reserve.config.max_borrow_rate = 100;
+    let mut data = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut data).unwrap();
+    data
+}
+
+fn with_reserve<T>(f: impl FnOnce(&AccountInfo) -> T) -> T {
+    let key = Pubkey::new_unique();
+    let owner = port_lending_id();
+    let mut lamports = 0;
+    let mut data = reserve_data();
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    f(&info)
+}
+
+#[test]
+fn current_borrow_rate_follows_the_curve() {
+    let rate = with_reserve(|info| port_accessor::reserve_current_borrow_rate(info).unwrap());
+    assert_eq!(rate, Rate::from_percent(5));
+}
+
+#[test]
+fn accrued_interest_matches_manual_compounding() {
+    let principal = Decimal::from(1_000_000_000u64);
+    let slot_growth = Rate::one()
+        .try_add(Rate::from_percent(5).try_div(SLOTS_PER_YEAR).unwrap())
+        .unwrap();
+    for slots in [0u64, 1, 2, 10, 100] {
+        let mut compounded = principal;
+        for _ in 0..slots {
+            compounded = compounded.try_mul(slot_growth).unwrap();
+        }
+        let expected = compounded.try_sub(principal).unwrap();
+        let accrued = with_reserve(|info| {
+            port_accessor::accrued_interest(info, principal, 1_000, 1_000 + slots).unwrap()
+        });
+        let (accrued, expected) = (
+            accrued.to_scaled_val().unwrap(),
+            expected.to_scaled_val().unwrap(),
+        );
+        // per-step rounding differs between repeated squaring and sequential
+        // multiplication by at most a few wad units per slot
+        assert!(
+            accrued.max(expected) - accrued.min(expected) <= 2 * slots as u128 * 1_000_000_000,
+            "{} slots: {} vs {}",
+            slots,
+            accrued,
+            expected
+        );
+    }
+    let accrued = with_reserve(|info| {
+        port_accessor::accrued_interest(info, principal, 1_000, 1_000).unwrap()
+    });
+    assert_eq!(accrued, Decimal::zero());
+}
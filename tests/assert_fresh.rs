@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use port_anchor_adaptor::error::PortAdaptorError;
+use port_anchor_adaptor::{assert_fresh, port_lending_id};
+use port_variable_rate_lending_instructions::state::{
+    LastUpdate, Obligation, Reserve, PROGRAM_VERSION,
+};
+
+const SLOT: u64 = 100;
+
+fn reserve_data(last_update: LastUpdate) -> Vec<u8> {
+    let reserve = Reserve {
+        version: PROGRAM_VERSION,
+        last_update,
+        ..Reserve::default()
+    };
+    let mut data = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut data).unwrap();
+    data
+}
+
+fn obligation_data(last_update: LastUpdate) -> Vec<u8> {
+    let obligation = Obligation {
+        version: PROGRAM_VERSION,
+        last_update,
+        ..Obligation::default()
+    };
+    let mut data = vec![0u8; Obligation::LEN];
+    Obligation::pack(obligation, &mut data).unwrap();
+    data
+}
+
+fn fresh() -> LastUpdate {
+    LastUpdate {
+        slot: SLOT,
+        stale: false,
+    }
+}
+
+fn check(
+    obligation: LastUpdate,
+    reserves: &[LastUpdate],
+) -> std::result::Result<(), anchor_lang::error::Error> {
+    let owner = port_lending_id();
+    let mut obligation_data = obligation_data(obligation);
+    let mut reserve_data: Vec<Vec<u8>> = reserves.iter().cloned().map(reserve_data).collect();
+    let keys: Vec<Pubkey> = reserves.iter().map(|_| Pubkey::new_unique()).collect();
+    let mut lamports = vec![0u64; reserves.len() + 1];
+    let (obligation_lamports, reserve_lamports) = lamports.split_first_mut().unwrap();
+    let obligation_key = Pubkey::new_unique();
+    let obligation_info = AccountInfo::new(
+        &obligation_key,
+        false,
+        true,
+        obligation_lamports,
+        &mut obligation_data,
+        &owner,
+        false,
+        0,
+    );
+    let reserve_infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(reserve_data.iter_mut())
+        .zip(reserve_lamports.iter_mut())
+        .map(|((key, data), lamports)| {
+            AccountInfo::new(key, false, true, lamports, data, &owner, false, 0)
+        })
+        .collect();
+    assert_fresh(&obligation_info, &reserve_infos, SLOT)
+}
+
+#[test]
+fn assert_fresh_passes_when_everything_is_fresh() {
+    assert!(check(fresh(), &[fresh(), fresh()]).is_ok());
+}
+
+#[test]
+fn assert_fresh_rejects_a_stale_reserve() {
+    let stale = LastUpdate {
+        slot: SLOT,
+        stale: true,
+    };
+    let outdated = LastUpdate {
+        slot: SLOT - 1,
+        stale: false,
+    };
+    for reserve in [stale, outdated] {
+        assert_eq!(
+            check(fresh(), &[fresh(), reserve]).unwrap_err(),
+            error!(PortAdaptorError::StaleReserve)
+        );
+    }
+}
+
+#[test]
+fn assert_fresh_rejects_a_stale_obligation() {
+    let outdated = LastUpdate {
+        slot: SLOT - 1,
+        stale: false,
+    };
+    assert_eq!(
+        check(outdated, &[fresh()]).unwrap_err(),
+        error!(PortAdaptorError::StaleObligation)
+    );
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+use port_anchor_adaptor::error::PortAdaptorError;
+use port_anchor_adaptor::test_utils::obligation_data;
+use port_anchor_adaptor::{borrow_checked, port_lending_id, Borrow};
+
+const OBLIGATION: usize = 6;
+const CLOCK: usize = 8;
+const PROGRAM: usize = 10;
+
+fn borrow(stale: bool) -> std::result::Result<(), anchor_lang::error::Error> {
+    let owner = port_lending_id();
+    let mut keys: Vec<Pubkey> = (0..=PROGRAM).map(|_| Pubkey::new_unique()).collect();
+    keys[CLOCK] = sysvar::clock::id();
+    keys[PROGRAM] = port_lending_id();
+    let mut lamports = vec![0u64; keys.len()];
+    let mut data = vec![Vec::<u8>::new(); keys.len()];
+    data[OBLIGATION] = obligation_data(stale, 0);
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut().zip(data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, false, true, lamports, data, &owner, false, 0)
+        })
+        .collect();
+    let accounts = Borrow {
+        source_liquidity: infos[0].clone(),
+        destination_liquidity: infos[1].clone(),
+        reserve: infos[2].clone(),
+        reserve_fee_receiver: infos[3].clone(),
+        lending_market: infos[4].clone(),
+        lending_market_authority: infos[5].clone(),
+        obligation: infos[OBLIGATION].clone(),
+        obligation_owner: infos[7].clone(),
+        clock: infos[CLOCK].clone(),
+        token_program: infos[9].clone(),
+    };
+    borrow_checked(CpiContext::new(infos[PROGRAM].clone(), accounts), 100)
+}
+
+#[test]
+fn rejects_a_stale_obligation() {
+    assert_eq!(
+        borrow(true).unwrap_err(),
+        error!(PortAdaptorError::StaleObligation)
+    );
+}
+
+#[test]
+fn borrows_against_a_refreshed_obligation() {
+    assert!(borrow(false).is_ok());
+}
@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+use port_anchor_adaptor::test_utils::record_cpis;
+use port_anchor_adaptor::{
+    claim_reward, claim_reward_instruction, staking_program_authority, ClaimReward,
+};
+
+const PROGRAM: usize = 8;
+
+#[test]
+fn built_instruction_matches_the_cpi_path() {
+    let program_id = Pubkey::new_unique();
+    let staking_pool = Pubkey::new_unique();
+    let keys = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        staking_pool,
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        staking_program_authority(&staking_pool, &program_id).0,
+        sysvar::clock::id(),
+        spl_token::id(),
+        program_id,
+        // sub reward token pool and destination
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let owner = Pubkey::default();
+    let mut lamports = [0u64; 11];
+    let mut data: [Vec<u8>; 11] = Default::default();
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut().zip(data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, false, false, lamports, data, &owner, false, 0)
+        })
+        .collect();
+    let (infos, sub_reward_infos) = infos.split_at(PROGRAM + 1);
+
+    for sub_reward_infos in [&[][..], sub_reward_infos] {
+        let accounts = ClaimReward {
+            stake_account_owner: infos[0].clone(),
+            stake_account: infos[1].clone(),
+            staking_pool: infos[2].clone(),
+            reward_token_pool: infos[3].clone(),
+            reward_dest: infos[4].clone(),
+            staking_program_authority: infos[5].clone(),
+            clock: infos[6].clone(),
+            token_program: infos[7].clone(),
+        };
+        let ctx = CpiContext::new(infos[PROGRAM].clone(), accounts)
+            .with_remaining_accounts(sub_reward_infos.to_vec());
+        let (result, cpis) = record_cpis(|| claim_reward(ctx));
+        result.unwrap();
+        assert_eq!(cpis.len(), 1);
+        let cpi = &cpis[0];
+
+        let sub_reward = match sub_reward_infos {
+            [pool, dest] => Some((pool.key(), dest.key())),
+            _ => None,
+        };
+        assert_eq!(
+            cpi.instruction,
+            claim_reward_instruction(
+                program_id, keys[0], keys[1], keys[2], keys[3], keys[4], sub_reward,
+            )
+        );
+
+        // the CPI hands invoke the eight accounts, the program, then the sub
+        // reward accounts, and every meta is backed by one of them
+        let expected_infos: Vec<Pubkey> = keys[..=PROGRAM]
+            .iter()
+            .chain(sub_reward_infos.iter().map(|info| info.key))
+            .copied()
+            .collect();
+        assert_eq!(cpi.account_infos, expected_infos);
+        for meta in &cpi.instruction.accounts {
+            assert!(expected_infos.contains(&meta.pubkey));
+        }
+        assert!(cpi.instruction.accounts[0].is_signer);
+        let sub_metas: Vec<Pubkey> = cpi.instruction.accounts[PROGRAM..]
+            .iter()
+            .map(|meta| meta.pubkey)
+            .collect();
+        assert_eq!(
+            sub_metas,
+            sub_reward_infos
+                .iter()
+                .map(|info| info.key())
+                .collect::<Vec<_>>()
+        );
+    }
+}
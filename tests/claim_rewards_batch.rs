@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+use port_anchor_adaptor::error::PortAdaptorError;
+use port_anchor_adaptor::{
+    claim_rewards_batch, port_staking_id, ClaimReward, MAX_CLAIM_REWARDS_BATCH,
+};
+
+struct TestAccount {
+    key: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+}
+
+impl TestAccount {
+    fn new(key: Pubkey) -> Self {
+        Self {
+            key,
+            lamports: 0,
+            data: vec![],
+        }
+    }
+
+    fn info<'a>(&'a mut self, owner: &'a Pubkey) -> AccountInfo<'a> {
+        AccountInfo::new(
+            &self.key,
+            false,
+            true,
+            &mut self.lamports,
+            &mut self.data,
+            owner,
+            false,
+            0,
+        )
+    }
+}
+
+const CLOCK: usize = 6;
+
+/// The eight `ClaimReward` accounts, with the clock sysvar in its slot.
+fn test_accounts() -> Vec<TestAccount> {
+    (0..8)
+        .map(|i| match i {
+            CLOCK => TestAccount::new(sysvar::clock::id()),
+            _ => TestAccount::new(Pubkey::new_unique()),
+        })
+        .collect()
+}
+
+fn claim_reward<'info>(accounts: &[AccountInfo<'info>; 8]) -> ClaimReward<'info> {
+    ClaimReward {
+        stake_account_owner: accounts[0].clone(),
+        stake_account: accounts[1].clone(),
+        staking_pool: accounts[2].clone(),
+        reward_token_pool: accounts[3].clone(),
+        reward_dest: accounts[4].clone(),
+        staking_program_authority: accounts[5].clone(),
+        clock: accounts[CLOCK].clone(),
+        token_program: accounts[7].clone(),
+    }
+}
+
+#[test]
+fn claims_from_two_pools() {
+    let owner = port_staking_id();
+    let mut program = TestAccount::new(port_staking_id());
+    let mut first = test_accounts();
+    let mut second = test_accounts();
+    let program = program.info(&owner);
+    let first: Vec<_> = first.iter_mut().map(|a| a.info(&owner)).collect();
+    let second: Vec<_> = second.iter_mut().map(|a| a.info(&owner)).collect();
+
+    let ctxs = [first, second]
+        .iter()
+        .map(|accounts| {
+            CpiContext::new(
+                program.clone(),
+                claim_reward(accounts.as_slice().try_into().unwrap()),
+            )
+        })
+        .collect();
+    assert!(claim_rewards_batch(ctxs).is_ok());
+}
+
+#[test]
+fn rejects_too_many_claims() {
+    let owner = port_staking_id();
+    let mut program = TestAccount::new(port_staking_id());
+    let mut accounts = test_accounts();
+    let program = program.info(&owner);
+    let accounts: Vec<_> = accounts.iter_mut().map(|a| a.info(&owner)).collect();
+    let accounts: &[AccountInfo; 8] = accounts.as_slice().try_into().unwrap();
+
+    let ctxs = (0..=MAX_CLAIM_REWARDS_BATCH)
+        .map(|_| CpiContext::new(program.clone(), claim_reward(accounts)))
+        .collect();
+    assert_eq!(
+        claim_rewards_batch(ctxs).unwrap_err(),
+        error!(PortAdaptorError::TooManyClaims)
+    );
+}
@@ -0,0 +1,14 @@
+use port_anchor_adaptor::{compute_budget, compute_budget_ix};
+
+#[test]
+fn sets_the_compute_unit_limit() {
+    let ix = compute_budget_ix(400_000);
+    assert_eq!(ix.program_id, compute_budget::id());
+    assert!(ix.accounts.is_empty());
+    // SetComputeUnitLimit discriminator followed by the little-endian units
+    assert_eq!(ix.data, vec![2, 0x80, 0x1a, 0x06, 0x00]);
+    assert_eq!(
+        compute_budget::id().to_string(),
+        "ComputeBudget111111111111111111111111111111"
+    );
+}
@@ -0,0 +1,24 @@
+use port_anchor_adaptor::{estimate_cpi_units, CpiKind};
+
+#[test]
+fn estimates_are_set_and_ordered() {
+    for kind in [
+        CpiKind::InitObligation,
+        CpiKind::Deposit,
+        CpiKind::DepositAndCollateralize,
+        CpiKind::Borrow,
+        CpiKind::Repay,
+        CpiKind::Withdraw,
+        CpiKind::Redeem,
+        CpiKind::RefreshReserve,
+        CpiKind::RefreshObligation { reserve_count: 0 },
+        CpiKind::ClaimReward,
+    ] {
+        assert!(estimate_cpi_units(kind) > 0);
+    }
+    assert!(
+        estimate_cpi_units(CpiKind::DepositAndCollateralize) > estimate_cpi_units(CpiKind::Deposit)
+    );
+    let refresh = |reserve_count| estimate_cpi_units(CpiKind::RefreshObligation { reserve_count });
+    assert!((0..10).all(|n| refresh(n) < refresh(n + 1)));
+}
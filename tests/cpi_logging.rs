@@ -0,0 +1,27 @@
+#![cfg(feature = "cpi-logging")]
+
+use anchor_lang::prelude::*;
+use port_anchor_adaptor::{create_stake_account, port_staking_id, CreateStakeAccount};
+
+#[test]
+fn logs_and_invokes_a_cpi() {
+    let owner = port_staking_id();
+    let mut keys: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+    keys[4] = port_staking_id();
+    let mut lamports = [0u64; 5];
+    let mut data: [Vec<u8>; 5] = Default::default();
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut().zip(data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, false, true, lamports, data, &owner, false, 0)
+        })
+        .collect();
+    let accounts = CreateStakeAccount {
+        staking_pool: infos[0].clone(),
+        stake_account: infos[1].clone(),
+        owner: infos[2].clone(),
+        rent: infos[3].clone(),
+    };
+    assert!(create_stake_account(CpiContext::new(infos[4].clone(), accounts)).is_ok());
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+use port_anchor_adaptor::{decode_staking_instruction, port_staking_id};
+use port_staking_instructions::instruction::{create_stake_account, StakingInstruction};
+
+#[test]
+fn decodes_create_stake_account() {
+    let ix = create_stake_account(
+        port_staking_id(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    assert!(matches!(
+        decode_staking_instruction(&ix.data).unwrap(),
+        StakingInstruction::CreateStakeAccount
+    ));
+}
+
+#[test]
+fn rejects_unknown_data() {
+    assert!(decode_staking_instruction(&[]).is_err());
+    assert!(decode_staking_instruction(&[u8::MAX]).is_err());
+}
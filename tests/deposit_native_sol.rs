@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::sysvar;
+use port_anchor_adaptor::error::PortAdaptorError;
+use port_anchor_adaptor::{deposit_native_sol, port_lending_id, DepositNativeSol};
+use port_variable_rate_lending_instructions::state::{Reserve, PROGRAM_VERSION};
+
+const NATIVE_MINT: usize = 2;
+const RESERVE: usize = 4;
+const CLOCK: usize = 9;
+const PROGRAM: usize = 13;
+
+fn deposit(
+    liquidity_mint: Pubkey,
+    native_mint: Pubkey,
+) -> std::result::Result<(), anchor_lang::error::Error> {
+    let owner = port_lending_id();
+    let mut keys: Vec<Pubkey> = (0..=PROGRAM).map(|_| Pubkey::new_unique()).collect();
+    keys[NATIVE_MINT] = native_mint;
+    keys[CLOCK] = sysvar::clock::id();
+    keys[PROGRAM] = port_lending_id();
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.liquidity.mint_pubkey = liquidity_mint;
+    let mut lamports = vec![0u64; keys.len()];
+    let mut data = vec![Vec::<u8>::new(); keys.len()];
+    data[RESERVE] = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut data[RESERVE]).unwrap();
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut().zip(data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, true, true, lamports, data, &owner, false, 0)
+        })
+        .collect();
+    let accounts = DepositNativeSol {
+        payer: infos[0].clone(),
+        wrapped_sol: infos[1].clone(),
+        native_mint: infos[NATIVE_MINT].clone(),
+        destination_collateral: infos[3].clone(),
+        reserve: infos[RESERVE].clone(),
+        reserve_liquidity_supply: infos[5].clone(),
+        reserve_collateral_mint: infos[6].clone(),
+        lending_market: infos[7].clone(),
+        lending_market_authority: infos[8].clone(),
+        clock: infos[CLOCK].clone(),
+        rent: infos[10].clone(),
+        token_program: infos[11].clone(),
+        system_program: infos[12].clone(),
+    };
+    deposit_native_sol(CpiContext::new(infos[PROGRAM].clone(), accounts), 100)
+}
+
+#[test]
+fn rejects_non_native_reserves() {
+    assert_eq!(
+        deposit(Pubkey::new_unique(), spl_token::native_mint::id()).unwrap_err(),
+        error!(PortAdaptorError::NotNativeMint)
+    );
+}
+
+#[test]
+fn rejects_a_mint_other_than_the_native_mint() {
+    assert_eq!(
+        deposit(spl_token::native_mint::id(), Pubkey::new_unique()).unwrap_err(),
+        error!(PortAdaptorError::NotNativeMint)
+    );
+}
+
+// Wrapping reads the rent sysvar and creates `wrapped_sol` through the system
+// program, so the native path runs against a local validator:
+//
+// 1. deploy Port lending, create a market with a native SOL reserve;
+// 2. call `deposit_native_sol` for one SOL from a test program with a fresh
+//    `wrapped_sol` keypair;
+// 3. expect the collateral for one SOL in `destination_collateral`, the payer
+//    down one SOL plus fees, and `wrapped_sol` closed.
+#[test]
+#[ignore = "needs a local validator with the Port programs deployed"]
+fn deposits_native_sol_end_to_end() {}
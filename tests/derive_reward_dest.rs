@@ -0,0 +1,21 @@
+use std::str::FromStr;
+
+use anchor_lang::prelude::*;
+use port_anchor_adaptor::derive_reward_dest;
+
+#[test]
+fn derive_reward_dest_matches_associated_token_address() {
+    let owner = Pubkey::new_from_array([1u8; 32]);
+    let reward_mint = Pubkey::from_str("PoRTjZMPXb9T7dyU7tpLEZRQj7e6ssfAE62j2oQuc6y").unwrap();
+    let associated_token_program =
+        Pubkey::from_str("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL").unwrap();
+    let (expected, _) = Pubkey::find_program_address(
+        &[
+            owner.as_ref(),
+            spl_token::id().as_ref(),
+            reward_mint.as_ref(),
+        ],
+        &associated_token_program,
+    );
+    assert_eq!(derive_reward_dest(&owner, &reward_mint), expected);
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use port_anchor_adaptor::{
+    claim_reward_instruction, deposit_account_metas, lending_market_authority, port_lending_id,
+    port_staking_id, staking_program_authority, DepositKeys,
+};
+
+#[test]
+fn claim_reward_targets_the_fork_and_its_authority() {
+    let fork = Pubkey::new_unique();
+    let staking_pool = Pubkey::new_unique();
+    let ix = claim_reward_instruction(
+        fork,
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        staking_pool,
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        None,
+    );
+    assert_eq!(ix.program_id, fork);
+    let keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+    assert!(keys.contains(&staking_program_authority(&staking_pool, &fork).0));
+    assert!(!keys.contains(&staking_program_authority(&staking_pool, &port_staking_id()).0));
+}
+
+#[test]
+fn deposit_metas_derive_the_fork_market_authority() {
+    let fork = Pubkey::new_unique();
+    let lending_market = Pubkey::new_unique();
+    let keys = DepositKeys {
+        source_liquidity: Pubkey::new_unique(),
+        destination_collateral: Pubkey::new_unique(),
+        reserve: Pubkey::new_unique(),
+        reserve_liquidity_supply: Pubkey::new_unique(),
+        reserve_collateral_mint: Pubkey::new_unique(),
+        lending_market,
+        transfer_authority: Pubkey::new_unique(),
+    };
+    let metas: Vec<Pubkey> = deposit_account_metas(fork, &keys)
+        .iter()
+        .map(|meta| meta.pubkey)
+        .collect();
+    assert!(metas.contains(&lending_market_authority(&lending_market, &fork).0));
+    assert!(!metas.contains(&lending_market_authority(&lending_market, &port_lending_id()).0));
+}
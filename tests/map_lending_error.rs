@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+use port_anchor_adaptor::{map_lending_error, with_lending_error_context};
+use port_variable_rate_lending_instructions::error::LendingError;
+
+#[test]
+fn map_lending_error_describes_known_codes() {
+    for err in [
+        LendingError::MathOverflow,
+        LendingError::ReserveStale,
+        LendingError::ObligationStale,
+        LendingError::InsufficientLiquidity,
+    ] {
+        assert_eq!(
+            map_lending_error(err.clone() as u32).map(str::to_string),
+            Some(err.to_string())
+        );
+    }
+    assert_eq!(map_lending_error(u32::MAX), None);
+}
+
+#[test]
+fn with_lending_error_context_passes_lending_errors_through() {
+    let code = LendingError::ReserveStale as u32;
+    let result: Result<()> = Err(ProgramError::Custom(code).into());
+    assert_eq!(
+        with_lending_error_context(result).unwrap_err(),
+        Error::from(ProgramError::Custom(code))
+    );
+    assert_eq!(with_lending_error_context(Ok(7)).unwrap(), 7);
+}
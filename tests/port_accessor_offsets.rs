@@ -0,0 +1,591 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use port_anchor_adaptor::port_accessor::ObligationEntryKind;
+use port_anchor_adaptor::{port_accessor, port_lending_id, PortReserve};
+use port_variable_rate_lending_instructions::math::{Decimal, WAD};
+use port_variable_rate_lending_instructions::state::{
+    LastUpdate, Obligation, ObligationCollateral, ObligationLiquidity, Reserve, ReserveCollateral,
+    ReserveLiquidity, MAX_OBLIGATION_RESERVES, PROGRAM_VERSION,
+};
+use proptest::prelude::*;
+use solana_maths::TryDiv;
+
+fn pubkey() -> impl Strategy<Value = Pubkey> {
+    any::<[u8; 32]>().prop_map(Pubkey::new_from_array)
+}
+
+fn decimal() -> impl Strategy<Value = Decimal> {
+    (0..u64::MAX as u128).prop_map(Decimal::from_scaled_val)
+}
+
+fn coption_pubkey() -> impl Strategy<Value = COption<Pubkey>> {
+    prop::option::of(pubkey()).prop_map(|key| match key {
+        Some(key) => COption::Some(key),
+        None => COption::None,
+    })
+}
+
+prop_compose! {
+    fn reserve_liquidity()(
+        mint_pubkey in pubkey(),
+        mint_decimals in any::<u8>(),
+        supply_pubkey in pubkey(),
+        fee_receiver in pubkey(),
+        oracle_pubkey in coption_pubkey(),
+        available_amount in any::<u64>(),
+        borrowed_amount_wads in decimal(),
+        cumulative_borrow_rate_wads in decimal(),
+        market_price in decimal(),
+    ) -> ReserveLiquidity {
+        ReserveLiquidity {
+            mint_pubkey,
+            mint_decimals,
+            supply_pubkey,
+            fee_receiver,
+            oracle_pubkey,
+            available_amount,
+            borrowed_amount_wads,
+            cumulative_borrow_rate_wads,
+            market_price,
+        }
+    }
+}
+
+prop_compose! {
+    fn reserve_collateral()(
+        mint_pubkey in pubkey(),
+        mint_total_supply in any::<u64>(),
+        supply_pubkey in pubkey(),
+    ) -> ReserveCollateral {
+        ReserveCollateral {
+            mint_pubkey,
+            mint_total_supply,
+            supply_pubkey,
+        }
+    }
+}
+
+prop_compose! {
+    fn reserve()(
+        slot in any::<u64>(),
+        stale in any::<bool>(),
+        lending_market in pubkey(),
+        liquidity in reserve_liquidity(),
+        collateral in reserve_collateral(),
+        loan_to_value_ratio in 0..=100u8,
+        borrow_fee_wad in any::<u64>(),
+        host_fee_percentage in 0..=100u8,
+        deposit_staking_pool in coption_pubkey(),
+    ) -> Reserve {
+        let mut reserve = Reserve {
+            version: PROGRAM_VERSION,
+            last_update: LastUpdate { slot, stale },
+            lending_market,
+            liquidity,
+            collateral,
+            ..Reserve::default()
+        };
+        reserve.config.loan_to_value_ratio = loan_to_value_ratio;
+        reserve.config.fees.borrow_fee_wad = borrow_fee_wad;
+        reserve.config.fees.host_fee_percentage = host_fee_percentage;
+        reserve.config.deposit_staking_pool = deposit_staking_pool;
+        reserve
+    }
+}
+
+prop_compose! {
+    fn obligation_collateral()(
+        deposit_reserve in pubkey(),
+        deposited_amount in any::<u64>(),
+        market_value in decimal(),
+    ) -> ObligationCollateral {
+        ObligationCollateral {
+            deposit_reserve,
+            deposited_amount,
+            market_value,
+        }
+    }
+}
+
+prop_compose! {
+    fn obligation_liquidity()(
+        borrow_reserve in pubkey(),
+        cumulative_borrow_rate_wads in decimal(),
+        borrowed_amount_wads in decimal(),
+        market_value in decimal(),
+    ) -> ObligationLiquidity {
+        ObligationLiquidity {
+            borrow_reserve,
+            cumulative_borrow_rate_wads,
+            borrowed_amount_wads,
+            market_value,
+        }
+    }
+}
+
+prop_compose! {
+    fn obligation()(deposits_len in 0..=MAX_OBLIGATION_RESERVES)(
+        slot in any::<u64>(),
+        stale in any::<bool>(),
+        lending_market in pubkey(),
+        owner in pubkey(),
+        deposits in prop::collection::vec(obligation_collateral(), deposits_len),
+        borrows in prop::collection::vec(
+            obligation_liquidity(),
+            0..=(MAX_OBLIGATION_RESERVES - deposits_len),
+        ),
+    ) -> Obligation {
+        Obligation {
+            version: PROGRAM_VERSION,
+            last_update: LastUpdate { slot, stale },
+            lending_market,
+            owner,
+            deposits,
+            borrows,
+            ..Obligation::default()
+        }
+    }
+}
+
+fn with_account_info<T>(data: &mut [u8], f: impl FnOnce(&AccountInfo) -> T) -> T {
+    let key = Pubkey::default();
+    let owner = port_lending_id();
+    let mut lamports = 0;
+    let info = AccountInfo::new(&key, false, true, &mut lamports, data, &owner, false, 0);
+    f(&info)
+}
+
+fn scaled(decimal: Decimal) -> u128 {
+    decimal.to_scaled_val().unwrap()
+}
+
+#[test]
+fn reserve_borrowed_amount_u64_rounds_up() {
+    for (wads, expected) in [(0, 0), (WAD as u128, 1), (5 * WAD as u128 + 1, 6)] {
+        let mut reserve = Reserve {
+            version: PROGRAM_VERSION,
+            ..Reserve::default()
+        };
+        reserve.liquidity.borrowed_amount_wads = Decimal::from_scaled_val(wads);
+        let mut data = vec![0u8; Reserve::LEN];
+        Reserve::pack(reserve, &mut data).unwrap();
+
+        let amount = with_account_info(&mut data, |info| {
+            port_accessor::reserve_borrowed_amount_u64(info)
+        });
+        assert_eq!(amount.unwrap(), expected);
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn reserve_mint_total_forwards_to_collateral_mint_supply() {
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.collateral.mint_total_supply = 1_234_567;
+    reserve.liquidity.available_amount = 7_654_321;
+    let mut data = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut data).unwrap();
+
+    with_account_info(&mut data, |info| {
+        let supply = port_accessor::reserve_collateral_mint_supply(info).unwrap();
+        assert_eq!(supply, 1_234_567);
+        assert_eq!(port_accessor::reserve_mint_total(info).unwrap(), supply);
+    });
+}
+
+#[test]
+fn parse_decimal_round_trips() {
+    let decimal = solana_maths::Decimal::from(1_234u64)
+        .try_div(solana_maths::Decimal::from(100u64))
+        .unwrap();
+    let bytes = decimal.to_scaled_val().unwrap().to_le_bytes();
+    assert_eq!(port_accessor::parse_decimal(&bytes), decimal);
+}
+
+#[test]
+fn reserve_is_native_matches_the_native_mint() {
+    for (mint, expected) in [
+        (spl_token::native_mint::id(), true),
+        (Pubkey::new_unique(), false),
+    ] {
+        let mut reserve = Reserve {
+            version: PROGRAM_VERSION,
+            ..Reserve::default()
+        };
+        reserve.liquidity.mint_pubkey = mint;
+        let mut data = vec![0u8; Reserve::LEN];
+        Reserve::pack(reserve, &mut data).unwrap();
+
+        let native = with_account_info(&mut data, |info| port_accessor::reserve_is_native(info));
+        assert_eq!(native.unwrap(), expected);
+    }
+}
+
+#[test]
+fn obligation_entry_exists_after_removal() {
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+    let mut obligation = Obligation {
+        version: PROGRAM_VERSION,
+        deposits: vec![
+            ObligationCollateral::new(first),
+            ObligationCollateral::new(second),
+        ],
+        borrows: vec![ObligationLiquidity::new(second)],
+        ..Obligation::default()
+    };
+
+    let exists = |obligation: &Obligation, reserve: &Pubkey, kind: ObligationEntryKind| {
+        let mut data = vec![0u8; Obligation::LEN];
+        Obligation::pack(obligation.clone(), &mut data).unwrap();
+        with_account_info(&mut data, |info| {
+            port_accessor::obligation_entry_exists(info, reserve, kind).unwrap()
+        })
+    };
+
+    assert!(exists(&obligation, &first, ObligationEntryKind::Deposit));
+    assert!(exists(&obligation, &second, ObligationEntryKind::Deposit));
+    assert!(exists(&obligation, &second, ObligationEntryKind::Borrow));
+    assert!(!exists(&obligation, &first, ObligationEntryKind::Borrow));
+
+    obligation.deposits.remove(0);
+    assert!(!exists(&obligation, &first, ObligationEntryKind::Deposit));
+    assert!(exists(&obligation, &second, ObligationEntryKind::Deposit));
+    assert!(exists(&obligation, &second, ObligationEntryKind::Borrow));
+}
+
+#[test]
+fn obligation_last_update_slot_rejects_short_data() {
+    let mut data = vec![0u8; 8];
+    assert!(with_account_info(&mut data, |info| {
+        port_accessor::obligation_last_update_slot(info)
+    })
+    .is_err());
+}
+
+#[test]
+fn reserve_last_update_slot_rejects_short_data() {
+    let mut data = vec![0u8; 8];
+    assert!(with_account_info(&mut data, |info| {
+        port_accessor::reserve_last_update_slot(info)
+    })
+    .is_err());
+}
+
+#[test]
+fn obligation_deposits_range_reads_a_window() {
+    let deposits: Vec<ObligationCollateral> = (0..10u64)
+        .map(|i| ObligationCollateral {
+            deposited_amount: 100 + i,
+            ..ObligationCollateral::new(Pubkey::new_unique())
+        })
+        .collect();
+    let obligation = Obligation {
+        version: PROGRAM_VERSION,
+        deposits: deposits.clone(),
+        ..Obligation::default()
+    };
+    let mut data = vec![0u8; Obligation::LEN];
+    Obligation::pack(obligation, &mut data).unwrap();
+
+    with_account_info(&mut data, |info| {
+        let window = port_accessor::obligation_deposits_range(info, 3, 7).unwrap();
+        let expected: Vec<(Pubkey, u64)> = deposits[3..7]
+            .iter()
+            .map(|d| (d.deposit_reserve, d.deposited_amount))
+            .collect();
+        assert_eq!(window, expected);
+        assert!(port_accessor::obligation_deposits_range(info, 10, 10)
+            .unwrap()
+            .is_empty());
+        assert!(port_accessor::obligation_deposits_range(info, 8, 11).is_err());
+        assert!(port_accessor::obligation_deposits_range(info, 5, 4).is_err());
+    });
+}
+
+#[test]
+fn is_price_stale_uses_the_refresh_slot() {
+    let reserve = Reserve {
+        version: PROGRAM_VERSION,
+        last_update: LastUpdate {
+            slot: 1_000,
+            stale: false,
+        },
+        ..Reserve::default()
+    };
+    let mut data = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut data).unwrap();
+
+    with_account_info(&mut data, |info| {
+        assert_eq!(
+            port_accessor::reserve_market_price_slot(info).unwrap(),
+            1_000
+        );
+        assert!(!port_accessor::is_price_stale(info, 1_010, 10).unwrap());
+        assert!(port_accessor::is_price_stale(info, 1_011, 10).unwrap());
+        assert!(!port_accessor::is_price_stale(info, 900, 10).unwrap());
+    });
+}
+
+#[test]
+fn unique_obligation_reserves_dedups_shared_reserves() {
+    let shared = Pubkey::new_unique();
+    let deposit_only = Pubkey::new_unique();
+    let borrow_only = Pubkey::new_unique();
+    let obligation = Obligation {
+        version: PROGRAM_VERSION,
+        deposits: vec![
+            ObligationCollateral::new(shared),
+            ObligationCollateral::new(deposit_only),
+        ],
+        borrows: vec![
+            ObligationLiquidity::new(borrow_only),
+            ObligationLiquidity::new(shared),
+        ],
+        ..Obligation::default()
+    };
+    let mut data = vec![0u8; Obligation::LEN];
+    Obligation::pack(obligation, &mut data).unwrap();
+
+    let reserves = with_account_info(&mut data, |info| {
+        port_accessor::unique_obligation_reserves(info).unwrap()
+    });
+    assert_eq!(reserves, vec![shared, deposit_only, borrow_only]);
+}
+
+#[test]
+fn reserve_pyth_product_reads_the_price_account() {
+    let oracle = Pubkey::new_unique();
+    let product = Pubkey::new_unique();
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.liquidity.oracle_pubkey = COption::Some(oracle);
+    let mut reserve_data = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut reserve_data).unwrap();
+
+    let mut price_data = vec![0u8; 240];
+    price_data[0..4].copy_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+    price_data[112..144].copy_from_slice(product.as_ref());
+
+    let (reserve_key, owner) = (Pubkey::new_unique(), port_lending_id());
+    let (mut reserve_lamports, mut price_lamports) = (0, 0);
+    let reserve_info = AccountInfo::new(
+        &reserve_key,
+        false,
+        false,
+        &mut reserve_lamports,
+        &mut reserve_data,
+        &owner,
+        false,
+        0,
+    );
+    let price_info = AccountInfo::new(
+        &oracle,
+        false,
+        false,
+        &mut price_lamports,
+        &mut price_data,
+        &owner,
+        false,
+        0,
+    );
+    assert_eq!(
+        port_accessor::reserve_pyth_product(&reserve_info, &price_info).unwrap(),
+        Some(product)
+    );
+    assert!(port_accessor::reserve_pyth_product(&reserve_info, &reserve_info).is_err());
+}
+
+proptest! {
+    #[test]
+    fn reserve_accessors_match_packed_fields(reserve in reserve()) {
+        let mut data = vec![0u8; Reserve::LEN];
+        Reserve::pack(reserve.clone(), &mut data).unwrap();
+
+        with_account_info(&mut data, |info| {
+            prop_assert_eq!(
+                port_accessor::reserve_ltv(info).unwrap(),
+                reserve.config.loan_to_value_ratio
+            );
+            prop_assert_eq!(
+                port_accessor::reserve_available_liquidity(info).unwrap(),
+                reserve.liquidity.available_amount
+            );
+            prop_assert_eq!(
+                port_accessor::reserve_borrowed_amount(info)
+                    .unwrap()
+                    .to_scaled_val()
+                    .unwrap(),
+                scaled(reserve.liquidity.borrowed_amount_wads)
+            );
+            prop_assert_eq!(
+                port_accessor::reserve_market_price(info)
+                    .unwrap()
+                    .to_scaled_val()
+                    .unwrap(),
+                scaled(reserve.liquidity.market_price)
+            );
+            prop_assert_eq!(
+                port_accessor::reserve_oracle_pubkey_opt(info).unwrap(),
+                match reserve.liquidity.oracle_pubkey {
+                    COption::Some(oracle) => Some(oracle),
+                    COption::None => None,
+                }
+            );
+            prop_assert_eq!(
+                port_accessor::reserve_staking_pool(info).unwrap(),
+                match reserve.config.deposit_staking_pool {
+                    COption::Some(staking_pool) => Some(staking_pool),
+                    COption::None => None,
+                }
+            );
+            prop_assert_eq!(
+                port_accessor::reserve_liquidity_mint_pubkey(info).unwrap(),
+                reserve.liquidity.mint_pubkey
+            );
+            prop_assert_eq!(
+                port_accessor::reserve_lp_mint_pubkey(info).unwrap(),
+                reserve.collateral.mint_pubkey
+            );
+            prop_assert_eq!(
+                port_accessor::reserve_collateral_mint_supply(info).unwrap(),
+                reserve.collateral.mint_total_supply
+            );
+            prop_assert_eq!(
+                port_accessor::reserve_borrow_fee(info)
+                    .unwrap()
+                    .to_scaled_val(),
+                reserve.config.fees.borrow_fee_wad as u128
+            );
+            prop_assert_eq!(
+                port_accessor::reserve_borrow_fee_receiver(info).unwrap(),
+                reserve.liquidity.fee_receiver
+            );
+            prop_assert_eq!(
+                port_accessor::reserve_host_fee_percentage(info).unwrap(),
+                reserve.config.fees.host_fee_percentage
+            );
+            prop_assert_eq!(
+                port_accessor::reserve_borrow_rate_index(info)
+                    .unwrap()
+                    .to_scaled_val()
+                    .unwrap(),
+                scaled(reserve.liquidity.cumulative_borrow_rate_wads)
+            );
+            prop_assert_eq!(
+                port_accessor::reserve_collateral_mint_decimals(info).unwrap(),
+                reserve.liquidity.mint_decimals
+            );
+            let liquidity = port_accessor::reserve_liquidity(info).unwrap();
+            prop_assert_eq!(liquidity.mint_pubkey, reserve.liquidity.mint_pubkey);
+            prop_assert_eq!(liquidity.mint_decimals, reserve.liquidity.mint_decimals);
+            prop_assert_eq!(liquidity.supply_pubkey, reserve.liquidity.supply_pubkey);
+            prop_assert_eq!(
+                liquidity.available_amount,
+                reserve.liquidity.available_amount
+            );
+            prop_assert_eq!(
+                liquidity.borrowed_amount_wads.to_scaled_val().unwrap(),
+                scaled(reserve.liquidity.borrowed_amount_wads)
+            );
+            prop_assert_eq!(
+                liquidity.market_price.to_scaled_val().unwrap(),
+                scaled(reserve.liquidity.market_price)
+            );
+            let collateral = port_accessor::reserve_collateral(info).unwrap();
+            prop_assert_eq!(collateral.mint_pubkey, reserve.collateral.mint_pubkey);
+            prop_assert_eq!(
+                collateral.mint_total_supply,
+                reserve.collateral.mint_total_supply
+            );
+            prop_assert_eq!(collateral.supply_pubkey, reserve.collateral.supply_pubkey);
+            prop_assert_eq!(
+                port_accessor::is_reserve_stale(info).unwrap(),
+                reserve.last_update.stale
+            );
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn reserve_exchange_rate_matches_accessor(reserve in reserve()) {
+        let mut data = vec![0u8; Reserve::LEN];
+        Reserve::pack(reserve, &mut data).unwrap();
+
+        let port_reserve = PortReserve::try_deserialize(&mut data.as_slice()).unwrap();
+        let from_reserve = port_reserve.exchange_rate().ok().map(|rate| rate.0);
+        let from_accessor = with_account_info(&mut data, |info| {
+            port_accessor::exchange_rate(info).ok().map(|rate| rate.0)
+        });
+        prop_assert_eq!(from_reserve, from_accessor);
+    }
+
+    #[test]
+    fn obligation_accessors_match_packed_fields(obligation in obligation()) {
+        let mut data = vec![0u8; Obligation::LEN];
+        Obligation::pack(obligation.clone(), &mut data).unwrap();
+
+        with_account_info(&mut data, |info| {
+            prop_assert_eq!(
+                port_accessor::obligation_deposits_count(info).unwrap() as usize,
+                obligation.deposits.len()
+            );
+            prop_assert_eq!(
+                port_accessor::obligation_borrows_count(info).unwrap() as usize,
+                obligation.borrows.len()
+            );
+            for (i, deposit) in obligation.deposits.iter().enumerate() {
+                prop_assert_eq!(
+                    port_accessor::obligation_deposit_amount(info, i as u8).unwrap(),
+                    deposit.deposited_amount
+                );
+                prop_assert_eq!(
+                    port_accessor::obligation_deposit_value(info, i as u8)
+                        .unwrap()
+                        .to_scaled_val()
+                        .unwrap(),
+                    scaled(deposit.market_value)
+                );
+            }
+            for (i, borrow) in obligation.borrows.iter().enumerate() {
+                prop_assert_eq!(
+                    port_accessor::obligation_borrow_amount_wads(info, i as u8)
+                        .unwrap()
+                        .to_scaled_val()
+                        .unwrap(),
+                    scaled(borrow.borrowed_amount_wads)
+                );
+            }
+            prop_assert!(port_accessor::obligation_deposit_amount(
+                info,
+                obligation.deposits.len() as u8
+            )
+            .is_err());
+            prop_assert!(port_accessor::obligation_deposit_value(
+                info,
+                obligation.deposits.len() as u8
+            )
+            .is_err());
+            prop_assert!(port_accessor::obligation_borrow_amount_wads(
+                info,
+                obligation.borrows.len() as u8
+            )
+            .is_err());
+            prop_assert_eq!(
+                port_accessor::obligation_last_update_slot(info).unwrap(),
+                obligation.last_update.slot
+            );
+            prop_assert_eq!(
+                port_accessor::is_obligation_stale(info).unwrap(),
+                obligation.last_update.stale
+            );
+            Ok(())
+        })?;
+    }
+}
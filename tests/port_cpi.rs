@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+use port_anchor_adaptor::test_utils::record_cpis;
+use port_anchor_adaptor::{port_staking_id, Borrow, Deposit, PortCpi};
+
+const CLOCK: usize = 8;
+const LENDING_PROGRAM: usize = 10;
+const STAKING_PROGRAM: usize = 11;
+
+#[test]
+fn deposits_then_borrows_through_one_port_cpi() {
+    // a fork of the lending program, so the CPIs can't fall back to Port's id
+    let lending_program = Pubkey::new_unique();
+    let mut keys: Vec<Pubkey> = (0..=STAKING_PROGRAM)
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    keys[CLOCK] = sysvar::clock::id();
+    keys[LENDING_PROGRAM] = lending_program;
+    keys[STAKING_PROGRAM] = port_staking_id();
+    let mut lamports = vec![0u64; keys.len()];
+    let mut data = vec![Vec::<u8>::new(); keys.len()];
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut().zip(data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, false, true, lamports, data, &lending_program, false, 0)
+        })
+        .collect();
+
+    let bump = [7u8];
+    let seeds: &[&[u8]] = &[b"vault", &bump];
+    let signer_seeds = &[seeds];
+    let cpi = PortCpi::new(
+        infos[LENDING_PROGRAM].clone(),
+        infos[STAKING_PROGRAM].clone(),
+        signer_seeds,
+    );
+
+    let (result, cpis) = record_cpis(|| {
+        cpi.deposit(
+            Deposit {
+                source_liquidity: infos[0].clone(),
+                destination_collateral: infos[1].clone(),
+                reserve: infos[2].clone(),
+                reserve_liquidity_supply: infos[3].clone(),
+                reserve_collateral_mint: infos[4].clone(),
+                lending_market: infos[5].clone(),
+                lending_market_authority: infos[6].clone(),
+                transfer_authority: infos[7].clone(),
+                clock: infos[CLOCK].clone(),
+                token_program: infos[9].clone(),
+            },
+            100,
+        )?;
+        cpi.borrow(
+            Borrow {
+                source_liquidity: infos[3].clone(),
+                destination_liquidity: infos[0].clone(),
+                reserve: infos[2].clone(),
+                reserve_fee_receiver: infos[9].clone(),
+                lending_market: infos[5].clone(),
+                lending_market_authority: infos[6].clone(),
+                obligation: infos[1].clone(),
+                obligation_owner: infos[7].clone(),
+                clock: infos[CLOCK].clone(),
+                token_program: infos[4].clone(),
+            },
+            40,
+        )
+    });
+    result.unwrap();
+
+    assert_eq!(cpis.len(), 2);
+    let expected_seeds = vec![vec![b"vault".to_vec(), bump.to_vec()]];
+    for cpi in &cpis {
+        assert_eq!(cpi.instruction.program_id, lending_program);
+        assert_eq!(cpi.signer_seeds, expected_seeds);
+    }
+}
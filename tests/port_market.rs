@@ -0,0 +1,169 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use port_anchor_adaptor::error::PortAdaptorError;
+use port_anchor_adaptor::{port_lending_id, PortLendingMarket, PortMarket, PortReserve};
+use port_variable_rate_lending_instructions::math::Decimal;
+use port_variable_rate_lending_instructions::state::{LendingMarket, Reserve, PROGRAM_VERSION};
+
+fn port_reserve(available_amount: u64, market_price: u64, oracle: bool) -> PortReserve {
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.liquidity.mint_decimals = 6;
+    reserve.liquidity.available_amount = available_amount;
+    reserve.liquidity.market_price = Decimal::from(market_price);
+    if oracle {
+        reserve.liquidity.oracle_pubkey = COption::Some(Pubkey::new_unique());
+    }
+    let mut data = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut data).unwrap();
+    PortReserve::try_deserialize(&mut data.as_slice()).unwrap()
+}
+
+fn port_market(reserves: Vec<PortReserve>) -> PortMarket {
+    let mut market_data = vec![0u8; LendingMarket::LEN];
+    LendingMarket::pack(
+        LendingMarket {
+            version: PROGRAM_VERSION,
+            ..LendingMarket::default()
+        },
+        &mut market_data,
+    )
+    .unwrap();
+    PortMarket {
+        key: Pubkey::new_unique(),
+        lending_market: PortLendingMarket::try_deserialize(&mut market_data.as_slice()).unwrap(),
+        reserves: reserves
+            .into_iter()
+            .map(|reserve| (Pubkey::new_unique(), reserve))
+            .collect(),
+    }
+}
+
+#[test]
+fn total_value_locked_prices_each_reserve() {
+    let market = port_market(vec![
+        port_reserve(3_000_000, 20, true),
+        port_reserve(500_000_000, 1, true),
+    ]);
+    assert_eq!(
+        market.total_value_locked(false).unwrap(),
+        Decimal::from(560u64)
+    );
+}
+
+#[test]
+fn total_value_locked_handles_unpriced_reserves() {
+    let market = port_market(vec![
+        port_reserve(3_000_000, 20, true),
+        port_reserve(7_000_000, 5, false),
+    ]);
+    assert_eq!(
+        market.total_value_locked(true).unwrap(),
+        Decimal::from(60u64)
+    );
+    assert_eq!(
+        market.total_value_locked(false).unwrap_err(),
+        error!(PortAdaptorError::MissingOracle)
+    );
+}
+
+fn load(
+    market_key: Pubkey,
+    reserve_markets: &[Pubkey],
+) -> std::result::Result<PortMarket, anchor_lang::error::Error> {
+    let owner = port_lending_id();
+    let mut market_data = vec![0u8; LendingMarket::LEN];
+    LendingMarket::pack(
+        LendingMarket {
+            version: PROGRAM_VERSION,
+            ..LendingMarket::default()
+        },
+        &mut market_data,
+    )
+    .unwrap();
+    let reserve_keys: Vec<Pubkey> = reserve_markets
+        .iter()
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    let mut reserve_data: Vec<Vec<u8>> = reserve_markets
+        .iter()
+        .map(|lending_market| {
+            let mut data = vec![0u8; Reserve::LEN];
+            Reserve::pack(
+                Reserve {
+                    version: PROGRAM_VERSION,
+                    lending_market: *lending_market,
+                    ..Reserve::default()
+                },
+                &mut data,
+            )
+            .unwrap();
+            data
+        })
+        .collect();
+    let mut market_lamports = 0;
+    let mut reserve_lamports = vec![0u64; reserve_markets.len()];
+    let market = AccountInfo::new(
+        &market_key,
+        false,
+        false,
+        &mut market_lamports,
+        &mut market_data,
+        &owner,
+        false,
+        0,
+    );
+    let reserves: Vec<AccountInfo> = reserve_keys
+        .iter()
+        .zip(reserve_lamports.iter_mut().zip(reserve_data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, false, false, lamports, data, &owner, false, 0)
+        })
+        .collect();
+    PortMarket::load(&market, &reserves, &port_lending_id())
+}
+
+#[test]
+fn load_collects_the_market_reserves() {
+    let market_key = Pubkey::new_unique();
+    let market = load(market_key, &[market_key, market_key]).unwrap();
+    assert_eq!(market.key, market_key);
+    assert_eq!(market.reserves.len(), 2);
+}
+
+#[test]
+fn load_rejects_a_reserve_from_another_market() {
+    let market_key = Pubkey::new_unique();
+    assert_eq!(
+        load(market_key, &[market_key, Pubkey::new_unique()])
+            .err()
+            .unwrap(),
+        error!(PortAdaptorError::ReserveMarketMismatch)
+    );
+}
+
+#[test]
+fn reserve_for_staking_pool_maps_pools_back_to_reserves() {
+    let staking_pool = Pubkey::new_unique();
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.config.deposit_staking_pool = COption::Some(staking_pool);
+    let mut data = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut data).unwrap();
+    let staked = PortReserve::try_deserialize(&mut data.as_slice()).unwrap();
+
+    let mut market = port_market(vec![port_reserve(100, 1, true)]);
+    let staked_key = Pubkey::new_unique();
+    market.reserves.push((staked_key, staked));
+
+    assert_eq!(
+        market.reserve_for_staking_pool(&staking_pool),
+        Some(staked_key)
+    );
+    assert_eq!(market.reserve_for_staking_pool(&Pubkey::new_unique()), None);
+}
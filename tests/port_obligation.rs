@@ -0,0 +1,460 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use port_anchor_adaptor::error::PortAdaptorError;
+use port_anchor_adaptor::{PortLendingMarket, PortMarket, PortObligation, PortReserve};
+use port_variable_rate_lending_instructions::math::{Decimal, WAD};
+use port_variable_rate_lending_instructions::state::{
+    LendingMarket, Obligation, ObligationCollateral, ObligationLiquidity, Reserve, PROGRAM_VERSION,
+};
+
+fn obligation_with_values(deposited_value: u64, borrowed_value: u64) -> PortObligation {
+    pack_obligation(Obligation {
+        version: PROGRAM_VERSION,
+        deposited_value: Decimal::from(deposited_value),
+        borrowed_value: Decimal::from(borrowed_value),
+        ..Obligation::default()
+    })
+}
+
+fn port_obligation(allowed_borrow_value: u64, borrowed_value: u64) -> PortObligation {
+    pack_obligation(Obligation {
+        version: PROGRAM_VERSION,
+        allowed_borrow_value: Decimal::from(allowed_borrow_value),
+        borrowed_value: Decimal::from(borrowed_value),
+        ..Obligation::default()
+    })
+}
+
+fn pack_obligation(obligation: Obligation) -> PortObligation {
+    let mut data = vec![0u8; Obligation::LEN];
+    Obligation::pack(obligation, &mut data).unwrap();
+    PortObligation::try_deserialize(&mut data.as_slice()).unwrap()
+}
+
+fn port_reserve(mint_decimals: u8, market_price: u64) -> PortReserve {
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.liquidity.mint_decimals = mint_decimals;
+    reserve.liquidity.market_price = Decimal::from(market_price);
+    let mut data = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut data).unwrap();
+    PortReserve::try_deserialize(&mut data.as_slice()).unwrap()
+}
+
+#[test]
+fn max_borrow_on_healthy_obligation() {
+    let obligation = port_obligation(100, 40);
+    assert_eq!(obligation.max_borrow_value(), Decimal::from(60u64));
+    assert_eq!(
+        obligation.max_borrow_amount(&port_reserve(6, 2)).unwrap(),
+        30_000_000
+    );
+}
+
+#[test]
+fn max_borrow_on_maxed_out_obligation() {
+    for obligation in [port_obligation(100, 100), port_obligation(100, 120)] {
+        assert_eq!(obligation.max_borrow_value(), Decimal::zero());
+        assert_eq!(
+            obligation.max_borrow_amount(&port_reserve(6, 2)).unwrap(),
+            0
+        );
+    }
+}
+
+#[test]
+fn total_values_sum_every_entry() {
+    let collateral = |market_value: u64| ObligationCollateral {
+        market_value: Decimal::from(market_value),
+        ..ObligationCollateral::new(Pubkey::new_unique())
+    };
+    let liquidity = |market_value: u64| ObligationLiquidity {
+        market_value: Decimal::from(market_value),
+        ..ObligationLiquidity::new(Pubkey::new_unique())
+    };
+    let obligation = pack_obligation(Obligation {
+        version: PROGRAM_VERSION,
+        deposits: vec![collateral(500), collateral(250)],
+        borrows: vec![liquidity(100), liquidity(40), liquidity(7)],
+        ..Obligation::default()
+    });
+    assert_eq!(
+        obligation.total_deposited_value().unwrap(),
+        Decimal::from(750u64)
+    );
+    assert_eq!(
+        obligation.total_borrowed_value().unwrap(),
+        Decimal::from(147u64)
+    );
+}
+
+#[test]
+fn fresh_obligation_is_empty_with_zero_totals() {
+    let obligation = pack_obligation(Obligation {
+        version: PROGRAM_VERSION,
+        ..Obligation::default()
+    });
+    assert!(obligation.is_empty());
+    assert_eq!(obligation.total_deposited_value().unwrap(), Decimal::zero());
+    assert_eq!(obligation.total_borrowed_value().unwrap(), Decimal::zero());
+    assert_eq!(obligation.max_borrow_value(), Decimal::zero());
+}
+
+#[test]
+fn from_fresh_sees_entries_added_after_a_snapshot() {
+    let pack = |deposits: usize| {
+        let obligation = Obligation {
+            version: PROGRAM_VERSION,
+            deposits: (0..deposits)
+                .map(|_| ObligationCollateral::new(Pubkey::new_unique()))
+                .collect(),
+            ..Obligation::default()
+        };
+        let mut data = vec![0u8; Obligation::LEN];
+        Obligation::pack(obligation, &mut data).unwrap();
+        data
+    };
+    let key = Pubkey::new_unique();
+    let owner = port_anchor_adaptor::port_lending_id();
+    let mut lamports = 0;
+    let mut data = pack(1);
+    let info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+
+    let snapshot = PortObligation::from_fresh(&info).unwrap();
+    assert_eq!(snapshot.deposit_count(), 1);
+
+    info.try_borrow_mut_data()
+        .unwrap()
+        .copy_from_slice(&pack(2));
+    assert_eq!(snapshot.deposit_count(), 1);
+    assert_eq!(
+        PortObligation::from_fresh(&info).unwrap().deposit_count(),
+        2
+    );
+}
+
+#[test]
+fn largest_entries_by_market_value() {
+    let deposits = vec![
+        ObligationCollateral {
+            market_value: Decimal::from(250u64),
+            ..ObligationCollateral::new(Pubkey::new_unique())
+        },
+        ObligationCollateral {
+            market_value: Decimal::from(900u64),
+            ..ObligationCollateral::new(Pubkey::new_unique())
+        },
+        ObligationCollateral {
+            market_value: Decimal::from(10u64),
+            ..ObligationCollateral::new(Pubkey::new_unique())
+        },
+    ];
+    let borrows = vec![
+        ObligationLiquidity {
+            market_value: Decimal::from(300u64),
+            ..ObligationLiquidity::new(Pubkey::new_unique())
+        },
+        ObligationLiquidity {
+            market_value: Decimal::from(40u64),
+            ..ObligationLiquidity::new(Pubkey::new_unique())
+        },
+    ];
+    let obligation = pack_obligation(Obligation {
+        version: PROGRAM_VERSION,
+        deposits: deposits.clone(),
+        borrows: borrows.clone(),
+        ..Obligation::default()
+    });
+    assert_eq!(
+        obligation.largest_deposit(),
+        Some((deposits[1].deposit_reserve, Decimal::from(900u64)))
+    );
+    assert_eq!(
+        obligation.largest_borrow(),
+        Some((borrows[0].borrow_reserve, Decimal::from(300u64)))
+    );
+
+    let empty = pack_obligation(Obligation {
+        version: PROGRAM_VERSION,
+        ..Obligation::default()
+    });
+    assert_eq!(empty.largest_deposit(), None);
+    assert_eq!(empty.largest_borrow(), None);
+}
+
+#[test]
+fn current_borrow_wads_accrues_between_indices() {
+    let reserve_key = Pubkey::new_unique();
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.liquidity.cumulative_borrow_rate_wads = Decimal::from_scaled_val(11 * WAD as u128 / 10);
+    let mut data = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut data).unwrap();
+    let reserve = PortReserve::try_deserialize(&mut data.as_slice()).unwrap();
+
+    let obligation = pack_obligation(Obligation {
+        version: PROGRAM_VERSION,
+        borrows: vec![ObligationLiquidity {
+            borrowed_amount_wads: Decimal::from(100u64),
+            ..ObligationLiquidity::new(reserve_key)
+        }],
+        ..Obligation::default()
+    });
+    assert_eq!(
+        obligation
+            .current_borrow_wads(&reserve_key, &reserve)
+            .unwrap(),
+        Decimal::from(110u64)
+    );
+    assert_eq!(
+        obligation
+            .current_borrow_wads(&Pubkey::new_unique(), &reserve)
+            .unwrap(),
+        Decimal::zero()
+    );
+}
+
+#[test]
+fn has_staked_collateral_looks_up_reserve_staking_pools() {
+    let pack_reserve = |staking_pool: COption<Pubkey>| {
+        let mut reserve = Reserve {
+            version: PROGRAM_VERSION,
+            ..Reserve::default()
+        };
+        reserve.config.deposit_staking_pool = staking_pool;
+        let mut data = vec![0u8; Reserve::LEN];
+        Reserve::pack(reserve, &mut data).unwrap();
+        PortReserve::try_deserialize(&mut data.as_slice()).unwrap()
+    };
+    let mut market_data = vec![0u8; LendingMarket::LEN];
+    LendingMarket::pack(
+        LendingMarket {
+            version: PROGRAM_VERSION,
+            ..LendingMarket::default()
+        },
+        &mut market_data,
+    )
+    .unwrap();
+    let (staked, unstaked) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let market = PortMarket {
+        key: Pubkey::new_unique(),
+        lending_market: PortLendingMarket::try_deserialize(&mut market_data.as_slice()).unwrap(),
+        reserves: vec![
+            (staked, pack_reserve(COption::Some(Pubkey::new_unique()))),
+            (unstaked, pack_reserve(COption::None)),
+        ],
+    };
+    let with_deposits = |reserves: &[Pubkey]| {
+        pack_obligation(Obligation {
+            version: PROGRAM_VERSION,
+            deposits: reserves
+                .iter()
+                .map(|reserve| ObligationCollateral::new(*reserve))
+                .collect(),
+            ..Obligation::default()
+        })
+    };
+
+    assert!(with_deposits(&[unstaked, staked]).has_staked_collateral(&market));
+    assert!(!with_deposits(&[unstaked]).has_staked_collateral(&market));
+    assert!(!with_deposits(&[]).has_staked_collateral(&market));
+}
+
+#[test]
+fn borrowing_power_by_collateral_reserve() {
+    let pack_reserve = |loan_to_value_ratio: u8| {
+        let mut reserve = Reserve {
+            version: PROGRAM_VERSION,
+            ..Reserve::default()
+        };
+        reserve.config.loan_to_value_ratio = loan_to_value_ratio;
+        let mut data = vec![0u8; Reserve::LEN];
+        Reserve::pack(reserve, &mut data).unwrap();
+        PortReserve::try_deserialize(&mut data.as_slice()).unwrap()
+    };
+    let mut market_data = vec![0u8; LendingMarket::LEN];
+    LendingMarket::pack(
+        LendingMarket {
+            version: PROGRAM_VERSION,
+            ..LendingMarket::default()
+        },
+        &mut market_data,
+    )
+    .unwrap();
+    let (sol, usdc, unused) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let market = PortMarket {
+        key: Pubkey::new_unique(),
+        lending_market: PortLendingMarket::try_deserialize(&mut market_data.as_slice()).unwrap(),
+        reserves: vec![
+            (sol, pack_reserve(75)),
+            (usdc, pack_reserve(85)),
+            (unused, pack_reserve(50)),
+        ],
+    };
+    let collateral = |deposit_reserve: Pubkey, market_value: u64| ObligationCollateral {
+        market_value: Decimal::from(market_value),
+        ..ObligationCollateral::new(deposit_reserve)
+    };
+    let obligation = pack_obligation(Obligation {
+        version: PROGRAM_VERSION,
+        deposits: vec![collateral(sol, 200), collateral(usdc, 100)],
+        ..Obligation::default()
+    });
+
+    let power = |key: &Pubkey| {
+        let (_, reserve) = market.reserves.iter().find(|(k, _)| k == key).unwrap();
+        obligation.borrowing_power_from(key, reserve).unwrap()
+    };
+    assert_eq!(power(&sol), Decimal::from(150u64));
+    assert_eq!(power(&usdc), Decimal::from(85u64));
+    assert_eq!(power(&unused), Decimal::zero());
+    assert_eq!(
+        obligation.borrowing_power(&market).unwrap(),
+        Decimal::from(235u64)
+    );
+}
+
+#[test]
+fn liquidation_accounts_target_largest_entries() {
+    let collateral = |market_value: u64| ObligationCollateral {
+        market_value: Decimal::from(market_value),
+        ..ObligationCollateral::new(Pubkey::new_unique())
+    };
+    let liquidity = |market_value: u64| ObligationLiquidity {
+        market_value: Decimal::from(market_value),
+        ..ObligationLiquidity::new(Pubkey::new_unique())
+    };
+    let deposits = vec![collateral(100), collateral(400)];
+    let borrows = vec![liquidity(350), liquidity(20)];
+    let obligation = pack_obligation(Obligation {
+        version: PROGRAM_VERSION,
+        deposits: deposits.clone(),
+        borrows: borrows.clone(),
+        ..Obligation::default()
+    });
+    assert_eq!(
+        obligation.liquidation_accounts().unwrap(),
+        (borrows[0].borrow_reserve, deposits[1].deposit_reserve)
+    );
+
+    let no_borrows = pack_obligation(Obligation {
+        version: PROGRAM_VERSION,
+        deposits,
+        ..Obligation::default()
+    });
+    assert_eq!(
+        no_borrows.liquidation_accounts().unwrap_err(),
+        error!(PortAdaptorError::BorrowIndexOutOfBound)
+    );
+    let no_deposits = pack_obligation(Obligation {
+        version: PROGRAM_VERSION,
+        borrows,
+        ..Obligation::default()
+    });
+    assert_eq!(
+        no_deposits.liquidation_accounts().unwrap_err(),
+        error!(PortAdaptorError::CollateralIndexOutOfBound)
+    );
+}
+
+#[test]
+fn max_withdraw_collateral_keeps_the_obligation_healthy() {
+    let reserve_key = Pubkey::new_unique();
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.config.loan_to_value_ratio = 50;
+    let mut data = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut data).unwrap();
+    let reserve = PortReserve::try_deserialize(&mut data.as_slice()).unwrap();
+    let obligation = |borrowed_value: u64| {
+        pack_obligation(Obligation {
+            version: PROGRAM_VERSION,
+            deposits: vec![ObligationCollateral {
+                deposited_amount: 1_000,
+                market_value: Decimal::from(200u64),
+                ..ObligationCollateral::new(reserve_key)
+            }],
+            allowed_borrow_value: Decimal::from(100u64),
+            borrowed_value: Decimal::from(borrowed_value),
+            ..Obligation::default()
+        })
+    };
+    let max_withdraw = |borrowed_value| {
+        obligation(borrowed_value)
+            .max_withdraw_collateral(&reserve_key, &reserve)
+            .unwrap()
+    };
+
+    assert_eq!(max_withdraw(0), 1_000);
+    assert_eq!(max_withdraw(40), 600);
+    assert_eq!(max_withdraw(99), 10);
+    assert_eq!(max_withdraw(100), 0);
+    assert_eq!(max_withdraw(120), 0);
+    assert_eq!(
+        obligation(40)
+            .max_withdraw_collateral(&Pubkey::new_unique(), &reserve)
+            .unwrap(),
+        0
+    );
+}
+
+#[test]
+fn net_value_of_solvent_obligation() {
+    assert_eq!(
+        obligation_with_values(100, 40).net_value().unwrap(),
+        Decimal::from(60u64)
+    );
+    assert_eq!(
+        obligation_with_values(40, 40).net_value().unwrap(),
+        Decimal::zero()
+    );
+}
+
+#[test]
+fn net_value_of_insolvent_obligation() {
+    assert_eq!(
+        obligation_with_values(40, 100).net_value().unwrap_err(),
+        error!(PortAdaptorError::Insolvency)
+    );
+}
+
+#[test]
+fn counts_deposits_and_borrows_separately() {
+    let obligation = pack_obligation(Obligation {
+        version: PROGRAM_VERSION,
+        deposits: (0..3)
+            .map(|_| ObligationCollateral::new(Pubkey::new_unique()))
+            .collect(),
+        borrows: vec![ObligationLiquidity::new(Pubkey::new_unique())],
+        ..Obligation::default()
+    });
+    assert_eq!(obligation.deposit_count(), 3);
+    assert_eq!(obligation.borrow_count(), 1);
+
+    let empty = pack_obligation(Obligation {
+        version: PROGRAM_VERSION,
+        ..Obligation::default()
+    });
+    assert_eq!(empty.deposit_count(), 0);
+    assert_eq!(empty.borrow_count(), 0);
+}
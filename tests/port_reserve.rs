@@ -0,0 +1,461 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use port_anchor_adaptor::error::PortAdaptorError;
+use port_anchor_adaptor::{lending_market_authority, port_accessor, port_lending_id, PortReserve};
+use port_variable_rate_lending_instructions::math::{Decimal, WAD};
+use port_variable_rate_lending_instructions::state::{Reserve, PROGRAM_VERSION};
+
+fn reserve_data(f: impl FnOnce(&mut Reserve)) -> Vec<u8> {
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    f(&mut reserve);
+    let mut data = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut data).unwrap();
+    data
+}
+
+fn port_reserve(f: impl FnOnce(&mut Reserve)) -> PortReserve {
+    PortReserve::try_deserialize(&mut reserve_data(f).as_slice()).unwrap()
+}
+
+#[test]
+fn can_borrow_up_to_available_liquidity() {
+    let reserve = port_reserve(|reserve| reserve.liquidity.available_amount = 100);
+    assert!(reserve.can_borrow(100).unwrap());
+    assert!(!reserve.can_borrow(101).unwrap());
+}
+
+#[test]
+fn can_borrow_accounts_for_borrow_fee() {
+    let reserve = port_reserve(|reserve| {
+        reserve.liquidity.available_amount = 100;
+        reserve.config.fees.borrow_fee_wad = WAD / 100;
+    });
+    assert!(reserve.can_borrow(99).unwrap());
+    assert!(!reserve.can_borrow(100).unwrap());
+}
+
+#[test]
+fn accessor_exchange_rate_matches_upstream() {
+    let cases = [
+        (0, 0, Decimal::zero()),
+        (1_000, 1_000, Decimal::zero()),
+        (3, 7, Decimal::zero()),
+        (
+            1_000_000,
+            999_999,
+            Decimal::from_scaled_val(WAD as u128 / 3),
+        ),
+        (123_456_789, 100_000_000, Decimal::from(23_456_789u64)),
+        (7, 1, Decimal::from(5u64)),
+    ];
+    for (mint_total_supply, available_amount, borrowed_amount_wads) in cases {
+        let mut reserve = Reserve {
+            version: PROGRAM_VERSION,
+            ..Reserve::default()
+        };
+        reserve.collateral.mint_total_supply = mint_total_supply;
+        reserve.liquidity.available_amount = available_amount;
+        reserve.liquidity.borrowed_amount_wads = borrowed_amount_wads;
+        let upstream = reserve.collateral_exchange_rate().unwrap();
+        let mut data = vec![0u8; Reserve::LEN];
+        Reserve::pack(reserve, &mut data).unwrap();
+
+        let (key, owner) = (Pubkey::new_unique(), port_lending_id());
+        let mut lamports = 0;
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        let accessor = port_accessor::exchange_rate(&info).unwrap();
+        assert_eq!(accessor.0, upstream.0);
+        assert_eq!(
+            accessor.collateral_to_liquidity(mint_total_supply).unwrap(),
+            upstream.collateral_to_liquidity(mint_total_supply).unwrap()
+        );
+        assert_eq!(
+            accessor.liquidity_to_collateral(available_amount).unwrap(),
+            upstream.liquidity_to_collateral(available_amount).unwrap()
+        );
+    }
+}
+
+#[test]
+fn max_borrowable_is_limited_by_liquidity() {
+    let reserve = port_reserve(|reserve| reserve.liquidity.available_amount = 100);
+    assert_eq!(reserve.max_borrowable().unwrap(), 100);
+
+    let reserve = port_reserve(|reserve| {
+        reserve.liquidity.available_amount = 100;
+        reserve.config.fees.borrow_fee_wad = WAD / 100;
+    });
+    let max_borrowable = reserve.max_borrowable().unwrap();
+    assert_eq!(max_borrowable, 99);
+    assert!(reserve.can_borrow(max_borrowable).unwrap());
+    assert!(!reserve.can_borrow(max_borrowable + 1).unwrap());
+
+    let reserve = port_reserve(|_| {});
+    assert_eq!(reserve.max_borrowable().unwrap(), 0);
+}
+
+#[test]
+fn deposit_accounts_are_taken_from_the_reserve() {
+    let lending_market = Pubkey::new_unique();
+    let (supply, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let fill = |reserve: &mut Reserve| {
+        reserve.lending_market = lending_market;
+        reserve.liquidity.supply_pubkey = supply;
+        reserve.collateral.mint_pubkey = mint;
+    };
+    let reserve = port_reserve(fill);
+    let reserve_key = Pubkey::new_unique();
+    let user_keys = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let pool_keys = [
+        Pubkey::new_unique(),
+        reserve.collateral.mint_pubkey,
+        lending_market_authority(&lending_market, &port_lending_id()).0,
+        anchor_lang::solana_program::sysvar::clock::ID,
+        reserve.liquidity.supply_pubkey,
+        spl_token::id(),
+        lending_market,
+    ];
+    let owner = Pubkey::default();
+    let mut lamports = [0u64; 11];
+    let mut data: [Vec<u8>; 11] = Default::default();
+    data[0] = reserve_data(fill);
+    let mut infos: Vec<AccountInfo> = std::iter::once(&reserve_key)
+        .chain(user_keys.iter())
+        .chain(pool_keys.iter())
+        .zip(lamports.iter_mut().zip(data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, false, false, lamports, data, &owner, false, 0)
+        })
+        .collect();
+    let pool = infos.split_off(4);
+
+    let deposit = reserve
+        .deposit_accounts(
+            infos[0].clone(),
+            infos[1].clone(),
+            infos[2].clone(),
+            infos[3].clone(),
+            &pool,
+            &port_lending_id(),
+        )
+        .unwrap();
+    assert_eq!(deposit.reserve.key(), reserve_key);
+    assert_eq!(deposit.source_liquidity.key(), user_keys[0]);
+    assert_eq!(deposit.destination_collateral.key(), user_keys[1]);
+    assert_eq!(deposit.transfer_authority.key(), user_keys[2]);
+    assert_eq!(
+        deposit.reserve_liquidity_supply.key(),
+        reserve.liquidity.supply_pubkey
+    );
+    assert_eq!(
+        deposit.reserve_collateral_mint.key(),
+        reserve.collateral.mint_pubkey
+    );
+    assert_eq!(deposit.lending_market.key(), lending_market);
+    assert_eq!(
+        deposit.lending_market_authority.key(),
+        lending_market_authority(&lending_market, &port_lending_id()).0
+    );
+    assert_eq!(deposit.token_program.key(), spl_token::id());
+
+    assert!(reserve
+        .deposit_accounts(
+            infos[0].clone(),
+            infos[1].clone(),
+            infos[2].clone(),
+            infos[3].clone(),
+            &pool[..3],
+            &port_lending_id(),
+        )
+        .is_err());
+}
+
+#[test]
+fn deposit_accounts_rejects_another_reserve() {
+    let reserve = port_reserve(|reserve| {
+        reserve.lending_market = Pubkey::new_unique();
+        reserve.liquidity.supply_pubkey = Pubkey::new_unique();
+        reserve.collateral.mint_pubkey = Pubkey::new_unique();
+    });
+    let other = reserve_data(|other| {
+        other.lending_market = reserve.lending_market;
+        other.liquidity.supply_pubkey = Pubkey::new_unique();
+        other.collateral.mint_pubkey = Pubkey::new_unique();
+    });
+    let keys = [Pubkey::new_unique(); 4];
+    let owner = Pubkey::default();
+    let mut lamports = [0u64; 4];
+    let mut data = [other, vec![], vec![], vec![]];
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut().zip(data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, false, false, lamports, data, &owner, false, 0)
+        })
+        .collect();
+
+    assert_eq!(
+        reserve
+            .deposit_accounts(
+                infos[0].clone(),
+                infos[1].clone(),
+                infos[2].clone(),
+                infos[3].clone(),
+                &[],
+                &port_lending_id(),
+            )
+            .err()
+            .unwrap(),
+        error!(PortAdaptorError::ReserveAccountMismatch)
+    );
+}
+
+#[test]
+fn borrow_fee_amount_splits_host_fee() {
+    let reserve = port_reserve(|reserve| {
+        reserve.config.fees.borrow_fee_wad = WAD / 100;
+        reserve.config.fees.host_fee_percentage = 20;
+    });
+    assert_eq!(
+        reserve.borrow_fee_amount(1_000_000).unwrap(),
+        (2_000, 10_000)
+    );
+    assert_eq!(reserve.borrow_fee_amount(1).unwrap(), (1, 1));
+
+    let reserve = port_reserve(|reserve| reserve.config.fees.borrow_fee_wad = WAD / 100);
+    assert_eq!(reserve.borrow_fee_amount(1).unwrap(), (0, 1));
+
+    let reserve = port_reserve(|_| {});
+    assert_eq!(reserve.borrow_fee_amount(1_000_000).unwrap(), (0, 0));
+}
+
+#[test]
+fn collateral_for_borrow_value_inverts_ltv() {
+    let reserve = port_reserve(|reserve| {
+        reserve.liquidity.mint_decimals = 6;
+        reserve.liquidity.market_price = Decimal::from(2u64);
+    });
+    let borrow_value = Decimal::from(50u64);
+    assert_eq!(
+        reserve
+            .collateral_for_borrow_value(borrow_value, 50)
+            .unwrap(),
+        50_000_000
+    );
+    assert_eq!(
+        reserve
+            .collateral_for_borrow_value(borrow_value, 80)
+            .unwrap(),
+        31_250_000
+    );
+    assert_eq!(
+        reserve
+            .collateral_for_borrow_value(borrow_value, 100)
+            .unwrap(),
+        25_000_000
+    );
+    assert_eq!(
+        reserve
+            .collateral_for_borrow_value(Decimal::from(1u64), 75)
+            .unwrap(),
+        666_667
+    );
+    assert_eq!(
+        reserve
+            .collateral_for_borrow_value(borrow_value, 0)
+            .unwrap_err(),
+        error!(PortAdaptorError::InvalidLtv)
+    );
+}
+
+#[test]
+fn collateral_for_borrow_value_rounds_up_at_the_exchange_rate() {
+    // one cToken is worth two tokens
+    let reserve = port_reserve(|reserve| {
+        reserve.liquidity.mint_decimals = 6;
+        reserve.liquidity.market_price = Decimal::from(2u64);
+        reserve.liquidity.available_amount = 1_500_000;
+        reserve.liquidity.borrowed_amount_wads = Decimal::from(500_000u64);
+        reserve.collateral.mint_total_supply = 1_000_000;
+    });
+    assert_eq!(
+        reserve
+            .collateral_for_borrow_value(Decimal::from(50u64), 100)
+            .unwrap(),
+        12_500_000
+    );
+    // 666_666.67 tokens are 333_333.33 cTokens
+    assert_eq!(
+        reserve
+            .collateral_for_borrow_value(Decimal::from(1u64), 75)
+            .unwrap(),
+        333_334
+    );
+}
+
+#[test]
+fn port_reserve_converts_into_reserve() {
+    let reserve = port_reserve(|reserve| reserve.liquidity.available_amount = 42);
+    let borrowed: &Reserve = reserve.as_ref();
+    assert_eq!(borrowed.liquidity.available_amount, 42);
+    let owned = Reserve::from(reserve.clone());
+    assert_eq!(&owned, borrowed);
+}
+
+#[test]
+fn snapshot_is_internally_consistent() {
+    let reserve = port_reserve(|reserve| {
+        reserve.liquidity.available_amount = 60;
+        reserve.liquidity.borrowed_amount_wads = Decimal::from(40u64);
+        reserve.config.optimal_utilization_rate = 80;
+        reserve.config.min_borrow_rate = 0;
+        reserve.config.optimal_borrow_rate = 10;
+        reserve.config.max_borrow_rate = 30;
+    });
+    let snapshot = reserve.snapshot().unwrap();
+    assert_eq!(snapshot.total_liquidity, Decimal::from(100u64));
+    assert_eq!(
+        snapshot.utilization_rate,
+        solana_maths::Rate::from_percent(40)
+    );
+    assert!(snapshot.supply_apy < snapshot.borrow_apy);
+    assert!(snapshot.borrow_apy > solana_maths::Rate::from_percent(5));
+
+    let idle = port_reserve(|reserve| reserve.liquidity.available_amount = 100).snapshot();
+    let idle = idle.unwrap();
+    assert_eq!(idle.utilization_rate, solana_maths::Rate::zero());
+    assert_eq!(idle.supply_apy, solana_maths::Rate::zero());
+}
+
+#[test]
+fn snapshot_diff_tracks_rate_and_liquidity_changes() {
+    let snapshot = |available_amount: u64, borrowed: u64| {
+        port_reserve(|reserve| {
+            reserve.liquidity.available_amount = available_amount;
+            reserve.liquidity.borrowed_amount_wads = Decimal::from(borrowed);
+            reserve.config.optimal_utilization_rate = 80;
+            reserve.config.optimal_borrow_rate = 10;
+            reserve.config.max_borrow_rate = 30;
+        })
+        .snapshot()
+        .unwrap()
+    };
+    let (previous, current) = (snapshot(80, 20), snapshot(40, 60));
+
+    let delta = current.diff(&previous).unwrap();
+    assert_eq!(delta.utilization_rate, (WAD * 2 / 5) as i128);
+    assert_eq!(delta.total_liquidity, 0);
+    assert!(delta.borrow_apy > 0);
+    assert!(delta.supply_apy > 0);
+
+    let reverse = previous.diff(&current).unwrap();
+    assert_eq!(reverse.utilization_rate, -delta.utilization_rate);
+    assert_eq!(reverse.borrow_apy, -delta.borrow_apy);
+    assert_eq!(current.diff(&current).unwrap().supply_apy, 0);
+}
+
+#[test]
+fn is_native_checks_the_liquidity_mint() {
+    let native =
+        port_reserve(|reserve| reserve.liquidity.mint_pubkey = spl_token::native_mint::id());
+    let usdc = port_reserve(|reserve| reserve.liquidity.mint_pubkey = Pubkey::new_unique());
+    assert!(native.is_native());
+    assert!(!usdc.is_native());
+}
+
+#[test]
+fn interest_rate_curve_hits_configured_rates() {
+    let reserve = port_reserve(|reserve| {
+        reserve.config.optimal_utilization_rate = 80;
+        reserve.config.min_borrow_rate = 2;
+        reserve.config.optimal_borrow_rate = 10;
+        reserve.config.max_borrow_rate = 150;
+    });
+    let curve = reserve.interest_rate_curve();
+    let at = |percent| {
+        curve
+            .evaluate(solana_maths::Rate::from_percent(percent))
+            .unwrap()
+    };
+    assert_eq!(at(0), solana_maths::Rate::from_percent(2));
+    assert_eq!(at(40), solana_maths::Rate::from_percent(6));
+    assert_eq!(at(80), solana_maths::Rate::from_percent(10));
+    assert_eq!(at(90), solana_maths::Rate::from_percent(80));
+    assert_eq!(at(100), solana_maths::Rate::from_percent(150));
+}
+
+#[test]
+fn needs_refresh_unless_updated_this_slot() {
+    let reserve = port_reserve(|reserve| {
+        reserve.last_update.slot = 10;
+        reserve.last_update.stale = false;
+    });
+    assert!(!reserve.needs_refresh(10));
+    assert!(reserve.needs_refresh(11));
+
+    let reserve = port_reserve(|reserve| {
+        reserve.last_update.slot = 10;
+        reserve.last_update.stale = true;
+    });
+    assert!(reserve.needs_refresh(10));
+}
+
+#[test]
+fn collateral_market_value_converts_then_prices() {
+    // 1_000_000 collateral against 2_000_000 liquidity: one cToken is worth two tokens
+    let reserve = port_reserve(|reserve| {
+        reserve.collateral.mint_total_supply = 1_000_000;
+        reserve.liquidity.available_amount = 1_500_000;
+        reserve.liquidity.borrowed_amount_wads = Decimal::from(500_000u64);
+        reserve.liquidity.mint_decimals = 6;
+        reserve.liquidity.market_price = Decimal::from(3u64);
+    });
+    assert_eq!(
+        reserve.collateral_market_value(1_000_000).unwrap(),
+        Decimal::from(6u64)
+    );
+    assert_eq!(
+        reserve.collateral_market_value(500_000).unwrap(),
+        Decimal::from(3u64)
+    );
+    assert_eq!(reserve.collateral_market_value(0).unwrap(), Decimal::zero());
+}
+
+#[test]
+fn net_amounts_convert_at_the_exchange_rate_without_fees() {
+    // one cToken is worth two tokens
+    let reserve = port_reserve(|reserve| {
+        reserve.collateral.mint_total_supply = 1_000_000;
+        reserve.liquidity.available_amount = 2_000_000;
+    });
+    assert_eq!(reserve.net_redeem_liquidity(500).unwrap(), 1_000);
+    assert_eq!(reserve.net_deposit_collateral(1_000).unwrap(), 500);
+}
+
+#[test]
+fn net_amounts_ignore_borrow_side_fees() {
+    let reserve = port_reserve(|reserve| {
+        reserve.collateral.mint_total_supply = 1_000_000;
+        reserve.liquidity.available_amount = 2_000_000;
+        reserve.config.fees.borrow_fee_wad = WAD / 100;
+        reserve.config.fees.host_fee_percentage = 20;
+    });
+    assert_eq!(reserve.net_redeem_liquidity(500).unwrap(), 1_000);
+    assert_eq!(reserve.net_deposit_collateral(1_000).unwrap(), 500);
+}
@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+use port_anchor_adaptor::{
+    lending_market_authority, lending_market_authority_signer_seeds, port_lending_id, PortSeeds,
+};
+
+#[test]
+fn with_signer_appends_bump() {
+    let seeds = PortSeeds::new(vec![b"obligation".to_vec(), vec![7u8; 32]], 254);
+    seeds.with_signer(|signer_seeds| {
+        assert_eq!(signer_seeds.len(), 1);
+        assert_eq!(signer_seeds[0].len(), 3);
+        assert_eq!(signer_seeds[0][0], b"obligation");
+        assert_eq!(signer_seeds[0][1], &[7u8; 32][..]);
+        assert_eq!(signer_seeds[0][2], &[254u8][..]);
+    });
+}
+
+#[test]
+fn lending_market_authority_seeds_derive_authority() {
+    let lending_market = Pubkey::new_unique();
+    let (authority, bump) = lending_market_authority(&lending_market, &port_lending_id());
+    let (seeds, seeds_bump) =
+        lending_market_authority_signer_seeds(&lending_market, &port_lending_id());
+    assert_eq!(seeds_bump, bump);
+    assert_eq!(
+        Pubkey::create_program_address(&seeds.seeds(), &port_lending_id()).unwrap(),
+        authority
+    );
+}
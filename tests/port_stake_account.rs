@@ -0,0 +1,42 @@
+use port_anchor_adaptor::{PortStakeAccount, PortStakingPool};
+use port_staking_instructions::math::Decimal;
+use port_staking_instructions::state::{StakeAccount, StakingPool};
+
+#[test]
+fn reward_indexes_surface_the_start_rates() {
+    let stake_account: PortStakeAccount = StakeAccount {
+        start_rate: Decimal::from(3u64),
+        sub_start_rate: Some(Decimal::from_scaled_val(42)),
+        ..StakeAccount::default()
+    }
+    .into();
+    assert_eq!(stake_account.last_reward_index(), Decimal::from(3u64));
+    assert_eq!(
+        stake_account.sub_reward_index(),
+        Some(Decimal::from_scaled_val(42))
+    );
+}
+
+#[test]
+fn sub_reward_index_is_none_without_a_sub_reward() {
+    let stake_account: PortStakeAccount = StakeAccount {
+        start_rate: Decimal::from(3u64),
+        sub_start_rate: None,
+        ..StakeAccount::default()
+    }
+    .into();
+    assert_eq!(stake_account.sub_reward_index(), None);
+}
+
+#[test]
+fn can_claim_from_the_earliest_reward_claim_time() {
+    let stake_account: PortStakeAccount = StakeAccount::default().into();
+    let pool: PortStakingPool = StakingPool {
+        earliest_reward_claim_time: 100,
+        ..StakingPool::default()
+    }
+    .into();
+    assert!(!stake_account.can_claim(&pool, 99));
+    assert!(stake_account.can_claim(&pool, 100));
+    assert!(stake_account.can_claim(&pool, 101));
+}
@@ -0,0 +1,158 @@
+use anchor_lang::prelude::Pubkey;
+use port_anchor_adaptor::{PortStakeAccount, PortStakingPool, RewardInfo};
+use port_staking_instructions::math::Decimal;
+use port_staking_instructions::state::{StakeAccount, StakingPool};
+use port_variable_rate_lending_instructions::math::Rate;
+
+/// A pool emitting over slots `[100, 200)`.
+fn staking_pool() -> PortStakingPool {
+    StakingPool {
+        end_time: 200,
+        duration: 100,
+        ..StakingPool::default()
+    }
+    .into()
+}
+
+#[test]
+fn ends_at_the_end_time() {
+    let pool = staking_pool();
+    assert_eq!(pool.ends_at_slot(), 200);
+    assert_eq!(pool.starts_at_slot(), 100);
+}
+
+#[test]
+fn is_active_within_the_reward_window() {
+    let pool = staking_pool();
+    assert!(pool.is_active(100));
+    assert!(pool.is_active(150));
+    assert!(pool.is_active(199));
+}
+
+#[test]
+fn is_not_active_before_start() {
+    let pool = staking_pool();
+    assert!(!pool.is_active(0));
+    assert!(!pool.is_active(99));
+}
+
+#[test]
+fn is_not_active_once_ended() {
+    let pool = staking_pool();
+    assert!(!pool.is_active(200));
+    assert!(!pool.is_active(250));
+}
+
+#[test]
+fn remaining_and_elapsed_slots_before_start() {
+    let pool = staking_pool();
+    assert_eq!(pool.remaining_slots(50), 150);
+    assert_eq!(pool.elapsed_slots(50), 0);
+}
+
+#[test]
+fn remaining_and_elapsed_slots_mid_window() {
+    let pool = staking_pool();
+    assert_eq!(pool.remaining_slots(150), 50);
+    assert_eq!(pool.elapsed_slots(150), 50);
+}
+
+#[test]
+fn remaining_and_elapsed_slots_after_end() {
+    let pool = staking_pool();
+    assert_eq!(pool.remaining_slots(200), 0);
+    assert_eq!(pool.elapsed_slots(200), 100);
+    assert_eq!(pool.remaining_slots(250), 0);
+    assert_eq!(pool.elapsed_slots(250), 100);
+}
+
+fn share(pool_size: u64, deposited_amount: u64) -> Rate {
+    let pool: PortStakingPool = StakingPool {
+        pool_size,
+        ..StakingPool::default()
+    }
+    .into();
+    let stake_account: PortStakeAccount = StakeAccount {
+        deposited_amount,
+        ..StakeAccount::default()
+    }
+    .into();
+    pool.share_of(&stake_account).unwrap()
+}
+
+#[test]
+fn share_of_an_empty_pool_is_zero() {
+    assert_eq!(share(0, 0), Rate::zero());
+}
+
+#[test]
+fn share_of_a_partial_stake() {
+    assert_eq!(share(400, 100), Rate::from_percent(25));
+}
+
+#[test]
+fn share_of_the_whole_pool_is_one() {
+    assert_eq!(share(400, 400), Rate::one());
+}
+
+#[test]
+fn rewards_of_a_single_reward_pool() {
+    let reward_token_pool = Pubkey::new_unique();
+    let pool: PortStakingPool = StakingPool {
+        reward_token_pool,
+        rate_per_slot: Decimal::from(5u64),
+        sub_reward_token_pool: None,
+        sub_rate_per_slot: None,
+        ..StakingPool::default()
+    }
+    .into();
+    assert_eq!(
+        pool.rewards(),
+        vec![RewardInfo {
+            reward_token_pool,
+            rate_per_slot: Decimal::from(5u64),
+        }]
+    );
+}
+
+#[test]
+fn rewards_of_a_dual_reward_pool() {
+    let (reward_token_pool, sub_reward_token_pool) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let pool: PortStakingPool = StakingPool {
+        reward_token_pool,
+        rate_per_slot: Decimal::from(5u64),
+        sub_reward_token_pool: Some(sub_reward_token_pool),
+        sub_rate_per_slot: Some(Decimal::from(2u64)),
+        ..StakingPool::default()
+    }
+    .into();
+    assert_eq!(
+        pool.rewards(),
+        vec![
+            RewardInfo {
+                reward_token_pool,
+                rate_per_slot: Decimal::from(5u64),
+            },
+            RewardInfo {
+                reward_token_pool: sub_reward_token_pool,
+                rate_per_slot: Decimal::from(2u64),
+            },
+        ]
+    );
+}
+
+fn rewards_per_slot_ui(rate_per_slot: u64, decimals: u8) -> f64 {
+    let pool: PortStakingPool = StakingPool {
+        rate_per_slot: Decimal::from(rate_per_slot),
+        ..StakingPool::default()
+    }
+    .into();
+    pool.rewards_per_slot_ui(decimals).unwrap()
+}
+
+#[test]
+fn rewards_per_slot_ui_scales_by_decimals() {
+    assert!((rewards_per_slot_ui(1_500_000, 6) - 1.5).abs() < 1e-12);
+    assert!((rewards_per_slot_ui(2_500_000_000, 9) - 2.5).abs() < 1e-12);
+    assert!((rewards_per_slot_ui(1_500_000, 9) - 0.0015).abs() < 1e-12);
+}
@@ -0,0 +1,65 @@
+use port_anchor_adaptor::rates::{apr_to_apy, net_leveraged_apy, slot_rate_to_apr};
+use solana_maths::{Rate, TryMul};
+
+fn times(n: u64) -> Rate {
+    Rate::one().try_mul(n).unwrap()
+}
+
+#[test]
+fn net_leveraged_apy_at_several_leverages() {
+    let (supply, borrow) = (Rate::from_percent(10), Rate::from_percent(4));
+    assert_eq!(net_leveraged_apy(supply, borrow, times(1)).unwrap(), supply);
+    assert_eq!(
+        net_leveraged_apy(supply, borrow, times(2)).unwrap(),
+        Rate::from_percent(16)
+    );
+    assert_eq!(
+        net_leveraged_apy(supply, borrow, times(3)).unwrap(),
+        Rate::from_percent(22)
+    );
+}
+
+#[test]
+fn net_leveraged_apy_floors_at_zero() {
+    assert_eq!(
+        net_leveraged_apy(Rate::from_percent(2), Rate::from_percent(10), times(2)).unwrap(),
+        Rate::zero()
+    );
+}
+
+#[test]
+fn slot_rate_to_apr_scales_by_slots() {
+    assert_eq!(
+        slot_rate_to_apr(Rate::from_scaled_val(1_000), 1_000).unwrap(),
+        Rate::from_scaled_val(1_000_000)
+    );
+    assert_eq!(
+        slot_rate_to_apr(Rate::from_percent(1), 0).unwrap(),
+        Rate::zero()
+    );
+}
+
+#[test]
+fn apr_to_apy_compounds_each_period() {
+    // (1 + 1/1)^1 - 1
+    assert_eq!(
+        apr_to_apy(Rate::from_percent(100), 1).unwrap(),
+        Rate::from_percent(100)
+    );
+    // (1 + 1/2)^2 - 1 = 1.25
+    assert_eq!(
+        apr_to_apy(Rate::from_percent(100), 2).unwrap(),
+        Rate::from_percent(125)
+    );
+    // (1 + 0.1/2)^2 - 1 = 0.1025
+    assert_eq!(
+        apr_to_apy(Rate::from_percent(10), 2).unwrap(),
+        Rate::from_scaled_val(102_500_000_000_000_000)
+    );
+    assert_eq!(apr_to_apy(Rate::zero(), 12).unwrap(), Rate::zero());
+}
+
+#[test]
+fn apr_to_apy_rejects_zero_compounding_periods() {
+    assert!(apr_to_apy(Rate::from_percent(10), 0).is_err());
+}
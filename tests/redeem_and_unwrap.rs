@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::sysvar;
+use port_anchor_adaptor::error::PortAdaptorError;
+use port_anchor_adaptor::{port_lending_id, redeem_and_unwrap, Redeem};
+use port_variable_rate_lending_instructions::state::{Reserve, PROGRAM_VERSION};
+
+const RESERVE: usize = 2;
+const TOKEN_PROGRAM: usize = 8;
+const CLOCK: usize = 9;
+const OWNER: usize = 10;
+const PROGRAM: usize = 11;
+
+fn redeem(liquidity_mint: Pubkey) -> std::result::Result<(), anchor_lang::error::Error> {
+    let owner = port_lending_id();
+    let mut keys: Vec<Pubkey> = (0..=PROGRAM).map(|_| Pubkey::new_unique()).collect();
+    keys[TOKEN_PROGRAM] = spl_token::id();
+    keys[CLOCK] = sysvar::clock::id();
+    keys[PROGRAM] = port_lending_id();
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.liquidity.mint_pubkey = liquidity_mint;
+    let mut lamports = vec![0u64; keys.len()];
+    let mut data = vec![Vec::<u8>::new(); keys.len()];
+    data[RESERVE] = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut data[RESERVE]).unwrap();
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut().zip(data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, false, true, lamports, data, &owner, false, 0)
+        })
+        .collect();
+    let accounts = Redeem {
+        source_collateral: infos[0].clone(),
+        destination_liquidity: infos[1].clone(),
+        reserve: infos[RESERVE].clone(),
+        reserve_collateral_mint: infos[3].clone(),
+        reserve_liquidity_supply: infos[4].clone(),
+        lending_market: infos[5].clone(),
+        lending_market_authority: infos[6].clone(),
+        transfer_authority: infos[7].clone(),
+        token_program: infos[TOKEN_PROGRAM].clone(),
+        clock: infos[CLOCK].clone(),
+    };
+    redeem_and_unwrap(
+        CpiContext::new(infos[PROGRAM].clone(), accounts),
+        infos[OWNER].clone(),
+        100,
+    )
+}
+
+#[test]
+fn redeems_and_unwraps_native_reserves() {
+    assert!(redeem(spl_token::native_mint::id()).is_ok());
+}
+
+#[test]
+fn rejects_non_native_reserves() {
+    assert_eq!(
+        redeem(Pubkey::new_unique()).unwrap_err(),
+        error!(PortAdaptorError::NotNativeMint)
+    );
+}
@@ -0,0 +1,17 @@
+use port_anchor_adaptor::{estimate_refresh_accounts_len, refresh_obligation_fits};
+
+#[test]
+fn refresh_accounts_len_counts_obligation_and_clock() {
+    assert_eq!(estimate_refresh_accounts_len(1), 3);
+    assert_eq!(estimate_refresh_accounts_len(6), 8);
+    assert_eq!(estimate_refresh_accounts_len(10), 12);
+}
+
+#[test]
+fn realistic_refreshes_fit_in_one_transaction() {
+    for reserve_count in [1, 6, 10] {
+        assert!(refresh_obligation_fits(reserve_count));
+    }
+    assert!(refresh_obligation_fits(30));
+    assert!(!refresh_obligation_fits(31));
+}
@@ -0,0 +1,161 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::sysvar;
+use port_anchor_adaptor::error::PortAdaptorError;
+use port_anchor_adaptor::{
+    port_lending_id, refresh_and_exchange_rate, refresh_port_reserve, OracleType, RefreshReserve,
+};
+use port_variable_rate_lending_instructions::math::Decimal;
+use port_variable_rate_lending_instructions::state::{Reserve, PROGRAM_VERSION};
+
+fn with_refresh_ctx<T>(
+    reserve: Reserve,
+    passed: &[Pubkey],
+    f: impl for<'a> FnOnce(CpiContext<'a, 'a, 'a, 'a, RefreshReserve<'a>>) -> T,
+) -> T {
+    let owner = port_lending_id();
+    let mut reserve_data = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut reserve_data).unwrap();
+    let (reserve_key, clock_key, program_key) =
+        (Pubkey::new_unique(), sysvar::clock::id(), port_lending_id());
+    let (mut reserve_lamports, mut clock_lamports, mut program_lamports) = (0, 0, 0);
+    let (mut clock_data, mut program_data) = (vec![], vec![]);
+    let mut oracle_lamports = vec![0u64; passed.len()];
+    let mut oracle_data = vec![Vec::<u8>::new(); passed.len()];
+
+    let reserve = AccountInfo::new(
+        &reserve_key,
+        false,
+        true,
+        &mut reserve_lamports,
+        &mut reserve_data,
+        &owner,
+        false,
+        0,
+    );
+    let clock = AccountInfo::new(
+        &clock_key,
+        false,
+        false,
+        &mut clock_lamports,
+        &mut clock_data,
+        &owner,
+        false,
+        0,
+    );
+    let program = AccountInfo::new(
+        &program_key,
+        false,
+        false,
+        &mut program_lamports,
+        &mut program_data,
+        &owner,
+        true,
+        0,
+    );
+    let oracles: Vec<AccountInfo> = passed
+        .iter()
+        .zip(oracle_lamports.iter_mut())
+        .zip(oracle_data.iter_mut())
+        .map(|((key, lamports), data)| {
+            AccountInfo::new(key, false, false, lamports, data, &owner, false, 0)
+        })
+        .collect();
+
+    f(
+        CpiContext::new(program, RefreshReserve { reserve, clock })
+            .with_remaining_accounts(oracles),
+    )
+}
+
+fn refresh(
+    oracle: Option<Pubkey>,
+    oracle_type: OracleType,
+    passed: &[Pubkey],
+) -> std::result::Result<(), anchor_lang::error::Error> {
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.liquidity.oracle_pubkey = oracle.map_or(COption::None, COption::Some);
+    with_refresh_ctx(reserve, passed, |ctx| {
+        refresh_port_reserve(ctx, oracle_type)
+    })
+}
+
+#[test]
+fn accounts_len_per_oracle_type() {
+    assert_eq!(OracleType::None.accounts_len(), 0);
+    assert_eq!(OracleType::Pyth.accounts_len(), 1);
+    assert_eq!(OracleType::Switchboard.accounts_len(), 1);
+}
+
+#[test]
+fn refreshes_with_each_oracle_type() {
+    assert!(refresh(None, OracleType::None, &[]).is_ok());
+    let oracle = Pubkey::new_unique();
+    assert!(refresh(Some(oracle), OracleType::Pyth, &[oracle]).is_ok());
+    assert!(refresh(Some(oracle), OracleType::Switchboard, &[oracle]).is_ok());
+}
+
+#[test]
+fn rejects_missing_oracle_accounts() {
+    let oracle = Pubkey::new_unique();
+    for oracle_type in [OracleType::Pyth, OracleType::Switchboard] {
+        assert_eq!(
+            refresh(Some(oracle), oracle_type, &[]).unwrap_err(),
+            error!(anchor_lang::error::ErrorCode::AccountNotEnoughKeys)
+        );
+    }
+}
+
+#[test]
+fn rejects_a_wrong_oracle() {
+    let oracle = Pubkey::new_unique();
+    assert_eq!(
+        refresh(Some(oracle), OracleType::Pyth, &[Pubkey::new_unique()]).unwrap_err(),
+        error!(PortAdaptorError::OracleMismatch)
+    );
+}
+
+#[test]
+fn skips_the_oracle_check_without_a_reserve_oracle() {
+    assert!(refresh(None, OracleType::Pyth, &[Pubkey::new_unique()]).is_ok());
+}
+
+#[test]
+fn reads_the_exchange_rate_after_refreshing() {
+    let oracle = Pubkey::new_unique();
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.liquidity.oracle_pubkey = COption::Some(oracle);
+    reserve.collateral.mint_total_supply = 1_000_000;
+    reserve.liquidity.available_amount = 1_500_000;
+    reserve.liquidity.borrowed_amount_wads = Decimal::from(500_000u64);
+    let expected = reserve.collateral_exchange_rate().unwrap();
+
+    let rate = with_refresh_ctx(reserve, &[oracle], |ctx| {
+        refresh_and_exchange_rate(ctx, OracleType::Pyth)
+    })
+    .unwrap();
+    assert_eq!(rate.0, expected.0);
+    assert_eq!(rate.collateral_to_liquidity(100).unwrap(), 200);
+}
+
+#[test]
+fn exchange_rate_is_not_read_when_the_refresh_fails() {
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.liquidity.oracle_pubkey = COption::Some(Pubkey::new_unique());
+    let err = with_refresh_ctx(reserve, &[Pubkey::new_unique()], |ctx| {
+        refresh_and_exchange_rate(ctx, OracleType::Pyth)
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, error!(PortAdaptorError::OracleMismatch));
+}
@@ -0,0 +1,118 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::sysvar::{self, Sysvar};
+use port_anchor_adaptor::error::PortAdaptorError;
+use port_anchor_adaptor::{port_lending_id, refresh_port_obligation_strict, RefreshObligation};
+use port_variable_rate_lending_instructions::state::{
+    LastUpdate, Obligation, Reserve, PROGRAM_VERSION,
+};
+
+const CURRENT_SLOT: Slot = 100;
+
+/// Refreshes an obligation at `CURRENT_SLOT`, forwarding one reserve per
+/// `(last_update_slot, stale)` pair.
+fn refresh(reserves: &[(Slot, bool)]) -> std::result::Result<(), anchor_lang::error::Error> {
+    let owner = port_lending_id();
+    let (obligation_key, clock_key, program_key) =
+        (Pubkey::new_unique(), sysvar::clock::id(), port_lending_id());
+    let (mut obligation_lamports, mut clock_lamports, mut program_lamports) = (0, 0, 0);
+    let mut obligation_data = vec![0u8; Obligation::LEN];
+    Obligation::pack(
+        Obligation {
+            version: PROGRAM_VERSION,
+            ..Obligation::default()
+        },
+        &mut obligation_data,
+    )
+    .unwrap();
+    let mut clock_data = vec![0u8; Clock::size_of()];
+    let mut program_data = vec![];
+    let reserve_keys: Vec<Pubkey> = reserves.iter().map(|_| Pubkey::new_unique()).collect();
+    let mut reserve_lamports = vec![0u64; reserves.len()];
+    let mut reserve_data: Vec<Vec<u8>> = reserves
+        .iter()
+        .map(|&(slot, stale)| {
+            let mut data = vec![0u8; Reserve::LEN];
+            Reserve::pack(
+                Reserve {
+                    version: PROGRAM_VERSION,
+                    last_update: LastUpdate { slot, stale },
+                    ..Reserve::default()
+                },
+                &mut data,
+            )
+            .unwrap();
+            data
+        })
+        .collect();
+
+    let obligation = AccountInfo::new(
+        &obligation_key,
+        false,
+        true,
+        &mut obligation_lamports,
+        &mut obligation_data,
+        &owner,
+        false,
+        0,
+    );
+    let mut clock = AccountInfo::new(
+        &clock_key,
+        false,
+        false,
+        &mut clock_lamports,
+        &mut clock_data,
+        &owner,
+        false,
+        0,
+    );
+    Clock {
+        slot: CURRENT_SLOT,
+        ..Clock::default()
+    }
+    .to_account_info(&mut clock)
+    .unwrap();
+    let program = AccountInfo::new(
+        &program_key,
+        false,
+        false,
+        &mut program_lamports,
+        &mut program_data,
+        &owner,
+        true,
+        0,
+    );
+    let reserve_infos: Vec<AccountInfo> = reserve_keys
+        .iter()
+        .zip(reserve_lamports.iter_mut().zip(reserve_data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, false, false, lamports, data, &owner, false, 0)
+        })
+        .collect();
+
+    let ctx = CpiContext::new(program, RefreshObligation { obligation, clock })
+        .with_remaining_accounts(reserve_infos);
+    refresh_port_obligation_strict(ctx)
+}
+
+#[test]
+fn refreshes_with_reserves_fresh_this_slot() {
+    assert!(refresh(&[]).is_ok());
+    assert!(refresh(&[(CURRENT_SLOT, false), (CURRENT_SLOT, false)]).is_ok());
+}
+
+#[test]
+fn rejects_a_reserve_flagged_stale() {
+    assert_eq!(
+        refresh(&[(CURRENT_SLOT, false), (CURRENT_SLOT, true)]).unwrap_err(),
+        error!(PortAdaptorError::StaleReserve)
+    );
+}
+
+#[test]
+fn rejects_a_reserve_refreshed_in_an_earlier_slot() {
+    assert_eq!(
+        refresh(&[(CURRENT_SLOT - 1, false)]).unwrap_err(),
+        error!(PortAdaptorError::StaleReserve)
+    );
+}
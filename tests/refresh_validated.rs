@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::sysvar;
+use port_anchor_adaptor::error::PortAdaptorError;
+use port_anchor_adaptor::{port_lending_id, refresh_port_obligation_validated, RefreshObligation};
+use port_variable_rate_lending_instructions::state::{
+    Obligation, ObligationCollateral, ObligationLiquidity, PROGRAM_VERSION,
+};
+
+fn refresh(
+    deposits: &[Pubkey],
+    borrows: &[Pubkey],
+    passed: &[Pubkey],
+) -> std::result::Result<(), anchor_lang::error::Error> {
+    let owner = port_lending_id();
+    let obligation = Obligation {
+        version: PROGRAM_VERSION,
+        deposits: deposits
+            .iter()
+            .map(|reserve| ObligationCollateral::new(*reserve))
+            .collect(),
+        borrows: borrows
+            .iter()
+            .map(|reserve| ObligationLiquidity::new(*reserve))
+            .collect(),
+        ..Obligation::default()
+    };
+    let mut obligation_data = vec![0u8; Obligation::LEN];
+    Obligation::pack(obligation, &mut obligation_data).unwrap();
+    let (obligation_key, clock_key, program_key) =
+        (Pubkey::new_unique(), sysvar::clock::id(), port_lending_id());
+    let (mut obligation_lamports, mut clock_lamports, mut program_lamports) = (0, 0, 0);
+    let (mut clock_data, mut program_data) = (vec![], vec![]);
+    let mut reserve_lamports = vec![0u64; passed.len()];
+    let mut reserve_data = vec![Vec::<u8>::new(); passed.len()];
+
+    let obligation = AccountInfo::new(
+        &obligation_key,
+        false,
+        true,
+        &mut obligation_lamports,
+        &mut obligation_data,
+        &owner,
+        false,
+        0,
+    );
+    let clock = AccountInfo::new(
+        &clock_key,
+        false,
+        false,
+        &mut clock_lamports,
+        &mut clock_data,
+        &owner,
+        false,
+        0,
+    );
+    let program = AccountInfo::new(
+        &program_key,
+        false,
+        false,
+        &mut program_lamports,
+        &mut program_data,
+        &owner,
+        true,
+        0,
+    );
+    let reserves: Vec<AccountInfo> = passed
+        .iter()
+        .zip(reserve_lamports.iter_mut())
+        .zip(reserve_data.iter_mut())
+        .map(|((key, lamports), data)| {
+            AccountInfo::new(key, false, false, lamports, data, &owner, false, 0)
+        })
+        .collect();
+
+    refresh_port_obligation_validated(
+        CpiContext::new(program, RefreshObligation { obligation, clock })
+            .with_remaining_accounts(reserves),
+    )
+}
+
+#[test]
+fn accepts_deposit_then_borrow_reserves() {
+    let (sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+    assert!(refresh(&[sol, usdc], &[sol], &[sol, usdc, sol]).is_ok());
+}
+
+#[test]
+fn rejects_mismatched_reserve_sets() {
+    let (sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+    for passed in [
+        vec![usdc, sol, sol],
+        vec![sol, usdc],
+        vec![sol, usdc, sol, usdc],
+        vec![sol, Pubkey::new_unique(), sol],
+    ] {
+        assert_eq!(
+            refresh(&[sol, usdc], &[sol], &passed).unwrap_err(),
+            error!(PortAdaptorError::ReserveSetMismatch)
+        );
+    }
+}
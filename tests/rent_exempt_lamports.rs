@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use port_anchor_adaptor::{
+    rent_exempt_lamports, PortAccountKind, PortLendingMarket, PortObligation, PortReserve,
+    PortStakeAccount, PortStakingPool,
+};
+use port_variable_rate_lending_instructions::state::{LendingMarket, Obligation, Reserve};
+
+#[test]
+fn rent_exempt_lamports_uses_account_len() {
+    let rent = Rent::default();
+    assert_eq!(PortReserve::LEN, Reserve::LEN);
+    assert_eq!(PortObligation::LEN, Obligation::LEN);
+    assert_eq!(PortLendingMarket::LEN, LendingMarket::LEN);
+    for (kind, len) in [
+        (PortAccountKind::LendingMarket, LendingMarket::LEN),
+        (PortAccountKind::Reserve, Reserve::LEN),
+        (PortAccountKind::Obligation, Obligation::LEN),
+        (PortAccountKind::StakingPool, PortStakingPool::LEN),
+        (PortAccountKind::StakeAccount, PortStakeAccount::LEN),
+    ] {
+        assert_eq!(rent_exempt_lamports(rent, kind), rent.minimum_balance(len));
+    }
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::Slot;
+use port_anchor_adaptor::{create_port_staking_pool, CreateStakingPoolContext, SlotDuration};
+
+// Compiles only while the duration and the claim slot are distinct types.
+#[allow(dead_code)]
+fn create_pool<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, CreateStakingPoolContext<'info>>,
+    earliest_reward_claim_time: Slot,
+) -> Result<()> {
+    create_port_staking_pool(
+        ctx,
+        1_000,
+        SlotDuration(432_000),
+        earliest_reward_claim_time,
+    )
+}
+
+#[test]
+fn slot_duration_from_u64() {
+    let duration: SlotDuration = 432_000u64.into();
+    assert_eq!(duration, SlotDuration(432_000));
+    assert_eq!(duration.slots(), 432_000);
+}
@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+use port_anchor_adaptor::test_utils::{record_cpis, RecordedCpi};
+use port_anchor_adaptor::{
+    create_stake_account_idempotent, port_staking_id, stake_account_exists, CreateStakeAccount,
+    PortStakeAccount,
+};
+use port_staking_instructions::instruction::create_stake_account;
+
+fn exists(owner: Pubkey, mut data: Vec<u8>) -> bool {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    stake_account_exists(&info, &port_staking_id()).unwrap()
+}
+
+#[test]
+fn initialized_stake_account_exists() {
+    let mut data = vec![0u8; PortStakeAccount::LEN];
+    data[0] = 1;
+    assert!(exists(port_staking_id(), data));
+}
+
+#[test]
+fn allocated_or_missing_stake_account_does_not_exist() {
+    assert!(!exists(port_staking_id(), vec![0u8; PortStakeAccount::LEN]));
+    assert!(!exists(
+        anchor_lang::solana_program::system_program::id(),
+        vec![]
+    ));
+    let mut foreign = vec![0u8; PortStakeAccount::LEN];
+    foreign[0] = 1;
+    assert!(!exists(Pubkey::new_unique(), foreign));
+}
+
+const STAKING_POOL: usize = 0;
+const STAKE_ACCOUNT: usize = 1;
+const OWNER: usize = 2;
+const PROGRAM: usize = 4;
+
+/// Runs `create_stake_account_idempotent` on a staking-program-owned stake
+/// account holding `stake_account`, returning the account keys and the CPIs it
+/// made.
+fn create_idempotent(stake_account: Vec<u8>) -> (Vec<Pubkey>, Vec<RecordedCpi>) {
+    let owner = port_staking_id();
+    let mut keys: Vec<Pubkey> = (0..=PROGRAM).map(|_| Pubkey::new_unique()).collect();
+    keys[PROGRAM] = port_staking_id();
+    let mut lamports = [0u64; 5];
+    let mut data: [Vec<u8>; 5] = Default::default();
+    data[STAKE_ACCOUNT] = stake_account;
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut().zip(data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, false, true, lamports, data, &owner, false, 0)
+        })
+        .collect();
+    let accounts = CreateStakeAccount {
+        staking_pool: infos[STAKING_POOL].clone(),
+        stake_account: infos[STAKE_ACCOUNT].clone(),
+        owner: infos[OWNER].clone(),
+        rent: infos[3].clone(),
+    };
+    let (result, cpis) = record_cpis(|| {
+        create_stake_account_idempotent(CpiContext::new(infos[PROGRAM].clone(), accounts))
+    });
+    result.unwrap();
+    (keys, cpis)
+}
+
+#[test]
+fn idempotent_create_skips_an_initialized_stake_account() {
+    let mut data = vec![0u8; PortStakeAccount::LEN];
+    data[0] = 1;
+    let (_, cpis) = create_idempotent(data);
+    assert!(cpis.is_empty());
+}
+
+#[test]
+fn idempotent_create_creates_an_empty_stake_account() {
+    let (keys, cpis) = create_idempotent(vec![0u8; PortStakeAccount::LEN]);
+    assert_eq!(cpis.len(), 1);
+    assert_eq!(
+        cpis[0].instruction,
+        create_stake_account(
+            port_staking_id(),
+            keys[STAKE_ACCOUNT],
+            keys[STAKING_POOL],
+            keys[OWNER],
+        )
+    );
+}
@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+use port_anchor_adaptor::{port_staking_id, staking_program_authority};
+
+#[test]
+fn staking_program_authority_derives_from_the_pool() {
+    let staking_pool = Pubkey::new_unique();
+    let (authority, bump) = staking_program_authority(&staking_pool, &port_staking_id());
+    assert_eq!(
+        Pubkey::create_program_address(&[staking_pool.as_ref(), &[bump]], &port_staking_id())
+            .unwrap(),
+        authority
+    );
+    assert_ne!(
+        staking_program_authority(&Pubkey::new_unique(), &port_staking_id()).0,
+        authority
+    );
+}
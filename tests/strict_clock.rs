@@ -0,0 +1,37 @@
+#![cfg(feature = "strict")]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+use port_anchor_adaptor::{port_lending_id, refresh_port_obligation, RefreshObligation};
+
+fn refresh(clock_key: Pubkey) -> std::result::Result<(), anchor_lang::error::Error> {
+    let owner = port_lending_id();
+    let keys = [Pubkey::new_unique(), clock_key, port_lending_id()];
+    let mut lamports = [0u64; 3];
+    let mut data: [Vec<u8>; 3] = Default::default();
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut().zip(data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, false, false, lamports, data, &owner, false, 0)
+        })
+        .collect();
+    let accounts = RefreshObligation {
+        obligation: infos[0].clone(),
+        clock: infos[1].clone(),
+    };
+    refresh_port_obligation(CpiContext::new(infos[2].clone(), accounts))
+}
+
+#[test]
+fn accepts_the_clock_sysvar() {
+    assert!(refresh(sysvar::clock::id()).is_ok());
+}
+
+#[test]
+fn rejects_a_spoofed_clock() {
+    assert_eq!(
+        refresh(Pubkey::new_unique()).unwrap_err(),
+        Error::from(ProgramError::InvalidArgument)
+    );
+}
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+use port_anchor_adaptor::test_utils::obligation_data;
+use port_anchor_adaptor::{port_accessor, port_lending_id};
+
+fn with_obligation<T>(stale: bool, slot: u64, f: impl FnOnce(&AccountInfo) -> T) -> T {
+    let key = Pubkey::new_unique();
+    let owner = port_lending_id();
+    let mut lamports = 0;
+    let mut data = obligation_data(stale, slot);
+    let info = AccountInfo::new(
+        &key,
+        false,
+        true,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    f(&info)
+}
+
+#[test]
+fn obligation_data_sets_the_stale_flag() {
+    assert!(with_obligation(true, 7, |info| {
+        port_accessor::is_obligation_stale(info).unwrap()
+    }));
+    assert!(!with_obligation(false, 7, |info| {
+        port_accessor::is_obligation_stale(info).unwrap()
+    }));
+}
+
+#[test]
+fn obligation_data_sets_the_last_update_slot() {
+    for slot in [0, 42, u64::MAX] {
+        assert_eq!(
+            with_obligation(false, slot, |info| {
+                port_accessor::obligation_last_update_slot(info).unwrap()
+            }),
+            slot
+        );
+    }
+}
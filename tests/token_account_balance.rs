@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use port_anchor_adaptor::token_account_balance;
+use spl_token::state::{Account, AccountState};
+
+fn token_account_data(amount: u64) -> Vec<u8> {
+    let account = Account {
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount,
+        state: AccountState::Initialized,
+        ..Account::default()
+    };
+    let mut data = vec![0u8; Account::LEN];
+    Account::pack(account, &mut data).unwrap();
+    data
+}
+
+#[test]
+fn reads_the_amount_of_a_token_account() {
+    let key = Pubkey::new_unique();
+    let owner = spl_token::id();
+    let mut lamports = 0;
+    let mut data = token_account_data(42);
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    assert_eq!(token_account_balance(&info).unwrap(), 42);
+}
+
+#[test]
+fn rejects_accounts_not_owned_by_the_token_program() {
+    let key = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = token_account_data(42);
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    assert!(token_account_balance(&info).is_err());
+}
@@ -0,0 +1,312 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use port_anchor_adaptor::error::PortAdaptorError;
+use port_anchor_adaptor::{
+    assert_owned_by, assert_port_owned, port_lending_id, port_staking_id, validate_clock, Borrow,
+    DepositAndCollateralize, Repay, Withdraw,
+};
+use port_variable_rate_lending_instructions::state::{Obligation, Reserve, PROGRAM_VERSION};
+
+fn deposit_and_collateralize<'info>(
+    account: &AccountInfo<'info>,
+    port_staking_program: &AccountInfo<'info>,
+) -> DepositAndCollateralize<'info> {
+    DepositAndCollateralize {
+        source_liquidity: account.clone(),
+        user_collateral: account.clone(),
+        reserve: account.clone(),
+        reserve_liquidity_supply: account.clone(),
+        reserve_collateral_mint: account.clone(),
+        lending_market: account.clone(),
+        lending_market_authority: account.clone(),
+        destination_collateral: account.clone(),
+        obligation: account.clone(),
+        obligation_owner: account.clone(),
+        stake_account: account.clone(),
+        staking_pool: account.clone(),
+        transfer_authority: account.clone(),
+        clock: account.clone(),
+        token_program: account.clone(),
+        port_staking_program: port_staking_program.clone(),
+    }
+}
+
+fn withdraw<'info>(
+    account: &AccountInfo<'info>,
+    port_staking_program: &AccountInfo<'info>,
+) -> Withdraw<'info> {
+    Withdraw {
+        source_collateral: account.clone(),
+        destination_collateral: account.clone(),
+        reserve: account.clone(),
+        obligation: account.clone(),
+        lending_market: account.clone(),
+        lending_market_authority: account.clone(),
+        stake_account: account.clone(),
+        staking_pool: account.clone(),
+        obligation_owner: account.clone(),
+        clock: account.clone(),
+        token_program: account.clone(),
+        port_staking_program: port_staking_program.clone(),
+    }
+}
+
+#[test]
+fn validated_checks_the_staking_program() {
+    let key = Pubkey::new_unique();
+    let staking_key = port_staking_id();
+    let owner = Pubkey::default();
+    let (mut lamports, mut staking_lamports) = (0, 0);
+    let (mut data, mut staking_data) = (vec![], vec![]);
+    let account = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    let staking_program = AccountInfo::new(
+        &staking_key,
+        false,
+        false,
+        &mut staking_lamports,
+        &mut staking_data,
+        &owner,
+        true,
+        0,
+    );
+
+    assert!(deposit_and_collateralize(&account, &staking_program)
+        .validated(&port_staking_id())
+        .is_ok());
+    assert!(withdraw(&account, &staking_program)
+        .validated(&port_staking_id())
+        .is_ok());
+    assert_eq!(
+        deposit_and_collateralize(&account, &account)
+            .validated(&port_staking_id())
+            .err()
+            .unwrap(),
+        error!(PortAdaptorError::WrongStakingProgram)
+    );
+    assert_eq!(
+        withdraw(&account, &account)
+            .validated(&port_staking_id())
+            .err()
+            .unwrap(),
+        error!(PortAdaptorError::WrongStakingProgram)
+    );
+    // a forked staking program is accepted when its id is passed
+    assert!(withdraw(&account, &account).validated(account.key).is_ok());
+}
+
+#[test]
+fn assert_owned_by_accepts_a_forked_program() {
+    let key = Pubkey::new_unique();
+    let fork = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = vec![];
+    let account = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &fork,
+        false,
+        0,
+    );
+
+    assert!(assert_owned_by(&account, &fork).is_ok());
+    assert!(assert_owned_by(&account, &port_lending_id()).is_err());
+    assert!(assert_port_owned(&account).is_err());
+}
+
+#[test]
+fn withdraw_requires_the_obligation_owner() {
+    let key = Pubkey::new_unique();
+    let staking_key = port_staking_id();
+    let owner = port_lending_id();
+    let pack = |obligation_owner: Pubkey| {
+        let mut data = vec![0u8; Obligation::LEN];
+        Obligation::pack(
+            Obligation {
+                version: PROGRAM_VERSION,
+                owner: obligation_owner,
+                ..Obligation::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        data
+    };
+    let (mut lamports, mut staking_lamports) = (0, 0);
+    let (mut data, mut staking_data) = (pack(Pubkey::new_unique()), vec![]);
+    let account = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    let staking_program = AccountInfo::new(
+        &staking_key,
+        false,
+        false,
+        &mut staking_lamports,
+        &mut staking_data,
+        &owner,
+        true,
+        0,
+    );
+
+    assert_eq!(
+        withdraw(&account, &staking_program)
+            .assert_obligation_owner()
+            .unwrap_err(),
+        error!(PortAdaptorError::UnauthorizedAuthority)
+    );
+    account
+        .try_borrow_mut_data()
+        .unwrap()
+        .copy_from_slice(&pack(key));
+    assert!(withdraw(&account, &staking_program)
+        .assert_obligation_owner()
+        .is_ok());
+}
+
+#[test]
+fn borrow_requires_the_reserve_fee_receiver() {
+    let fee_receiver = Pubkey::new_unique();
+    let keys = [Pubkey::new_unique(), fee_receiver, Pubkey::new_unique()];
+    let owner = port_lending_id();
+    let mut reserve = Reserve {
+        version: PROGRAM_VERSION,
+        ..Reserve::default()
+    };
+    reserve.liquidity.fee_receiver = fee_receiver;
+    let mut lamports = [0u64; 3];
+    let mut data: [Vec<u8>; 3] = Default::default();
+    data[0] = vec![0u8; Reserve::LEN];
+    Reserve::pack(reserve, &mut data[0]).unwrap();
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut().zip(data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, false, true, lamports, data, &owner, false, 0)
+        })
+        .collect();
+    let borrow = |fee_receiver: usize| Borrow {
+        source_liquidity: infos[2].clone(),
+        destination_liquidity: infos[2].clone(),
+        reserve: infos[0].clone(),
+        reserve_fee_receiver: infos[fee_receiver].clone(),
+        lending_market: infos[2].clone(),
+        lending_market_authority: infos[2].clone(),
+        obligation: infos[2].clone(),
+        obligation_owner: infos[2].clone(),
+        clock: infos[2].clone(),
+        token_program: infos[2].clone(),
+    };
+
+    assert!(borrow(1).assert_fee_receiver().is_ok());
+    assert_eq!(
+        borrow(2).assert_fee_receiver().unwrap_err(),
+        error!(PortAdaptorError::FeeReceiverMismatch)
+    );
+}
+
+#[test]
+fn repay_requires_the_source_owner() {
+    let (source_key, authority_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut source_data = vec![0u8; spl_token::state::Account::LEN];
+    spl_token::state::Account::pack(
+        spl_token::state::Account {
+            owner: Pubkey::new_unique(),
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        },
+        &mut source_data,
+    )
+    .unwrap();
+    let token_program = spl_token::id();
+    let (mut source_lamports, mut authority_lamports) = (0, 0);
+    let mut authority_data = vec![];
+    let source = AccountInfo::new(
+        &source_key,
+        false,
+        true,
+        &mut source_lamports,
+        &mut source_data,
+        &token_program,
+        false,
+        0,
+    );
+    let authority = AccountInfo::new(
+        &authority_key,
+        true,
+        false,
+        &mut authority_lamports,
+        &mut authority_data,
+        &token_program,
+        false,
+        0,
+    );
+    let repay = Repay {
+        source_liquidity: source.clone(),
+        destination_liquidity: source.clone(),
+        reserve: source.clone(),
+        obligation: source.clone(),
+        lending_market: source.clone(),
+        transfer_authority: authority,
+        clock: source.clone(),
+        token_program: source,
+    };
+    assert_eq!(
+        repay.assert_transfer_authority().unwrap_err(),
+        error!(PortAdaptorError::UnauthorizedAuthority)
+    );
+}
+
+#[test]
+fn validate_clock_rejects_other_accounts() {
+    let (clock_key, spoofed_key) = (
+        anchor_lang::solana_program::sysvar::clock::id(),
+        Pubkey::new_unique(),
+    );
+    let owner = anchor_lang::solana_program::sysvar::id();
+    let (mut clock_lamports, mut spoofed_lamports) = (0, 0);
+    let (mut clock_data, mut spoofed_data) = (vec![], vec![]);
+    let clock = AccountInfo::new(
+        &clock_key,
+        false,
+        false,
+        &mut clock_lamports,
+        &mut clock_data,
+        &owner,
+        false,
+        0,
+    );
+    let spoofed = AccountInfo::new(
+        &spoofed_key,
+        false,
+        false,
+        &mut spoofed_lamports,
+        &mut spoofed_data,
+        &owner,
+        false,
+        0,
+    );
+
+    assert!(validate_clock(&clock).is_ok());
+    assert_eq!(
+        validate_clock(&spoofed).unwrap_err(),
+        Error::from(ProgramError::InvalidArgument)
+    );
+}
@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::sysvar;
+use port_anchor_adaptor::{port_lending_id, withdraw_respecting_stake, PortStakeAccount, Withdraw};
+use port_staking_instructions::state::{StakeAccount, PROGRAM_VERSION};
+
+const STAKE_ACCOUNT: usize = 6;
+const CLOCK: usize = 9;
+const PROGRAM: usize = 12;
+
+/// Withdraws `amount` against a stake account holding `staked`, with every
+/// account owned by `owner`.
+fn withdraw(staked: u64, amount: u64, owner: Pubkey) -> Result<u64> {
+    let mut keys: Vec<Pubkey> = (0..=PROGRAM).map(|_| Pubkey::new_unique()).collect();
+    keys[CLOCK] = sysvar::clock::id();
+    keys[PROGRAM] = port_lending_id();
+    let mut lamports = vec![0u64; keys.len()];
+    let mut data = vec![Vec::<u8>::new(); keys.len()];
+    data[STAKE_ACCOUNT] = vec![0u8; PortStakeAccount::LEN];
+    StakeAccount::pack(
+        StakeAccount {
+            version: PROGRAM_VERSION,
+            deposited_amount: staked,
+            ..StakeAccount::default()
+        },
+        &mut data[STAKE_ACCOUNT],
+    )
+    .unwrap();
+    let infos: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut().zip(data.iter_mut()))
+        .map(|(key, (lamports, data))| {
+            AccountInfo::new(key, false, true, lamports, data, &owner, false, 0)
+        })
+        .collect();
+    let accounts = Withdraw {
+        source_collateral: infos[0].clone(),
+        destination_collateral: infos[1].clone(),
+        reserve: infos[2].clone(),
+        obligation: infos[3].clone(),
+        lending_market: infos[4].clone(),
+        lending_market_authority: infos[5].clone(),
+        stake_account: infos[STAKE_ACCOUNT].clone(),
+        staking_pool: infos[7].clone(),
+        obligation_owner: infos[8].clone(),
+        clock: infos[CLOCK].clone(),
+        token_program: infos[10].clone(),
+        port_staking_program: infos[11].clone(),
+    };
+    withdraw_respecting_stake(CpiContext::new(infos[PROGRAM].clone(), accounts), amount)
+}
+
+#[test]
+fn caps_the_withdraw_at_the_staked_amount() {
+    assert_eq!(withdraw(100, 250, port_lending_id()).unwrap(), 100);
+}
+
+#[test]
+fn passes_smaller_withdraws_through() {
+    assert_eq!(withdraw(100, 40, port_lending_id()).unwrap(), 40);
+    assert_eq!(withdraw(100, 100, port_lending_id()).unwrap(), 100);
+}
+
+#[test]
+fn skips_the_withdraw_without_stake() {
+    // foreign-owned accounts would fail the withdraw, so Ok(0) means no CPI
+    assert!(withdraw(100, 40, Pubkey::new_unique()).is_err());
+    assert_eq!(withdraw(0, 40, Pubkey::new_unique()).unwrap(), 0);
+}